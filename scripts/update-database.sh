@@ -0,0 +1,31 @@
+#!/bin/sh
+# Replaces the builtin subset with the full CHIP-8 database, rebuild afterwards to compile it in.
+# `update-database.sh <commit>` vendors programs.json of that commit and pins it in data/programs.lock,
+# without an argument the pinned commit is downloaded again and checked against its SHA-1
+set -e
+
+data="$(dirname "$0")/../data"
+out="$data/programs.json"
+lock="$data/programs.lock"
+
+if [ -n "$1" ]; then
+    commit="$1"
+elif [ -f "$lock" ]; then
+    read -r commit sha1 < "$lock"
+else
+    echo "No database is pinned yet, pass a commit of https://github.com/chip-8/chip-8-database" >&2
+    exit 1
+fi
+
+url="https://raw.githubusercontent.com/chip-8/chip-8-database/$commit/database/programs.json"
+curl -fsSL "$url" -o "$out.tmp"
+
+if [ -z "$1" ] && [ "$(sha1sum "$out.tmp" | cut -d' ' -f1)" != "$sha1" ]; then
+    rm "$out.tmp"
+    echo "programs.json of $commit doesn't match $lock" >&2
+    exit 1
+fi
+
+mv "$out.tmp" "$out"
+echo "$commit $(sha1sum "$out" | cut -d' ' -f1)" > "$lock"
+echo "Updated $out to $commit"
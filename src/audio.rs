@@ -0,0 +1,36 @@
+use sdl2::audio::{AudioQueue, AudioSpecDesired};
+use sdl2::Sdl;
+
+use crate::chip8::{Chip8, REFRESH_RATE};
+use crate::rng::Rng;
+
+const SAMPLE_RATE: i32 = 44100;
+// Frames of sound queued ahead so a late frame doesn't run dry
+const QUEUED_FRAMES: usize = 2;
+
+// Plays the MegaChip samples and the XO-CHIP pattern through a queue topped up once per frame
+pub struct Audio {
+    queue: AudioQueue<u8>,
+    buffer: Vec<u8>,
+}
+
+impl Audio {
+    pub fn new(sdl_context: &Sdl) -> Result<Self, String> {
+        let spec = AudioSpecDesired { freq: Some(SAMPLE_RATE), channels: Some(1), samples: None };
+        let queue = sdl_context.audio()?.open_queue::<u8, _>(None, &spec)?;
+        queue.resume();
+        Ok(Audio { queue, buffer: Vec::new() })
+    }
+
+    pub fn update<R: Rng>(&mut self, chip8: &mut Chip8<R>) -> Result<(), String> {
+        let rate = self.queue.spec().freq as u32;
+        let frame = (rate / REFRESH_RATE) as usize;
+        // one byte per sample on a single channel
+        while (self.queue.size() as usize) < frame * QUEUED_FRAMES {
+            self.buffer.resize(frame, 0);
+            chip8.fill_audio(&mut self.buffer, rate);
+            self.queue.queue_audio(&self.buffer)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+// RCA CDP1802 as found in the COSMAC VIP, enough to run the machine code routines called through 0NNN.
+// Every instruction takes 2 machine cycles, long branches and skips take 3.
+
+use crate::keys::Keys;
+
+const MEMORY_MASK: u16 = 0x0fff;
+const RETURN_REGISTER: u8 = 4;
+const KEYPAD_PORT: u8 = 2;
+
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cdp1802 {
+    r: [u16; 16],
+    p: u8,
+    x: u8,
+    d: u8,
+    df: bool,
+    t: u8,
+    ie: bool,
+    q: bool,
+    keypad_latch: u8,
+}
+
+impl Cdp1802 {
+    pub fn register(&self, n: u8) -> u16 {
+        self.r[n as usize]
+    }
+
+    pub fn set_register(&mut self, n: u8, value: u16) {
+        self.r[n as usize] = value;
+    }
+
+    // Runs the routine at addr with P = 3 and X = 2 like the VIP interpreter does, the routine returns with SEP R4.
+    // Returns the machine cycles taken or None if the routine didn't return within max_cycles.
+    pub fn call(&mut self, memory: &mut [u8; 4096], addr: u16, keys: Keys, max_cycles: u32) -> Option<u32> {
+        self.r[3] = addr;
+        self.p = 3;
+        self.x = 2;
+        self.ie = true;
+
+        let mut cycles = 0;
+        while self.p != RETURN_REGISTER {
+            if cycles >= max_cycles { return None; }
+            cycles += self.step(memory, keys);
+        }
+        Some(cycles)
+    }
+
+    fn read(&self, memory: &[u8; 4096], addr: u16) -> u8 {
+        memory[(addr & MEMORY_MASK) as usize]
+    }
+
+    fn write(&self, memory: &mut [u8; 4096], addr: u16, value: u8) {
+        memory[(addr & MEMORY_MASK) as usize] = value;
+    }
+
+    fn immediate(&mut self, memory: &[u8; 4096]) -> u8 {
+        let value = self.read(memory, self.r[self.p as usize]);
+        self.r[self.p as usize] = self.r[self.p as usize].wrapping_add(1);
+        value
+    }
+
+    // EF3 is wired to the keypad, it is set while the latched key is held
+    fn flag(&self, n: u8, keys: Keys) -> bool {
+        n == 3 && keys.contains(self.keypad_latch)
+    }
+
+    fn step(&mut self, memory: &mut [u8; 4096], keys: Keys) -> u32 {
+        let opcode = self.immediate(memory);
+        let n = opcode & 0xf;
+        let (rn, rx, rp) = (n as usize, self.x as usize, self.p as usize);
+
+        match opcode >> 4 {
+            // IDL waits for DMA or an interrupt, neither is emulated
+            0x0 if n == 0 => {}
+            0x0 => self.d = self.read(memory, self.r[rn]),
+            0x1 => self.r[rn] = self.r[rn].wrapping_add(1),
+            0x2 => self.r[rn] = self.r[rn].wrapping_sub(1),
+            0x3 => {
+                let taken = match n {
+                    0x0 => true,
+                    0x1 => self.q,
+                    0x2 => self.d == 0,
+                    0x3 => self.df,
+                    0x4..=0x7 => self.flag(n - 3, keys),
+                    0x8 => false,
+                    0x9 => !self.q,
+                    0xa => self.d != 0,
+                    0xb => !self.df,
+                    _ => !self.flag(n - 0xb, keys),
+                };
+                if taken {
+                    let low = self.read(memory, self.r[rp]);
+                    self.r[rp] = (self.r[rp] & 0xff00) | low as u16;
+                } else {
+                    self.r[rp] = self.r[rp].wrapping_add(1);
+                }
+            }
+            0x4 => {
+                self.d = self.read(memory, self.r[rn]);
+                self.r[rn] = self.r[rn].wrapping_add(1);
+            }
+            0x5 => self.write(memory, self.r[rn], self.d),
+            0x6 => match n {
+                0x0 => self.r[rx] = self.r[rx].wrapping_add(1),
+                0x1..=0x7 => {
+                    if n == KEYPAD_PORT { self.keypad_latch = self.read(memory, self.r[rx]) & 0xf }
+                    self.r[rx] = self.r[rx].wrapping_add(1);
+                }
+                0x8 => {}
+                // nothing is connected to the input ports
+                _ => {
+                    self.d = 0;
+                    self.write(memory, self.r[rx], 0);
+                }
+            },
+            0x7 => self.execute_7n(memory, n),
+            0x8 => self.d = self.r[rn] as u8,
+            0x9 => self.d = (self.r[rn] >> 8) as u8,
+            0xa => self.r[rn] = (self.r[rn] & 0xff00) | self.d as u16,
+            0xb => self.r[rn] = (self.r[rn] & 0x00ff) | (self.d as u16) << 8,
+            0xc => {
+                self.execute_long(memory, n);
+                return 3;
+            }
+            0xd => self.p = n,
+            0xe => self.x = n,
+            _ => self.execute_fn(memory, n),
+        }
+
+        2
+    }
+
+    fn execute_7n(&mut self, memory: &mut [u8; 4096], n: u8) {
+        let rx = self.x as usize;
+        match n {
+            0x0 | 0x1 => {
+                let xp = self.read(memory, self.r[rx]);
+                self.r[rx] = self.r[rx].wrapping_add(1);
+                self.x = xp >> 4;
+                self.p = xp & 0xf;
+                self.ie = n == 0x0;
+            }
+            0x2 => {
+                self.d = self.read(memory, self.r[rx]);
+                self.r[rx] = self.r[rx].wrapping_add(1);
+            }
+            0x3 => {
+                self.write(memory, self.r[rx], self.d);
+                self.r[rx] = self.r[rx].wrapping_sub(1);
+            }
+            0x4 => self.add(self.read(memory, self.r[rx]), self.df),
+            0x5 => self.subtract(self.read(memory, self.r[rx]), self.d, self.df),
+            0x6 => {
+                let carry = self.d & 1 != 0;
+                self.d = (self.d >> 1) | (self.df as u8) << 7;
+                self.df = carry;
+            }
+            0x7 => self.subtract(self.d, self.read(memory, self.r[rx]), self.df),
+            0x8 => self.write(memory, self.r[rx], self.t),
+            0x9 => {
+                self.t = self.x << 4 | self.p;
+                self.write(memory, self.r[2], self.t);
+                self.x = self.p;
+                self.r[2] = self.r[2].wrapping_sub(1);
+            }
+            0xa => self.q = false,
+            0xb => self.q = true,
+            0xc => {
+                let value = self.immediate(memory);
+                self.add(value, self.df);
+            }
+            0xd => {
+                let value = self.immediate(memory);
+                self.subtract(value, self.d, self.df);
+            }
+            0xe => {
+                let carry = self.d & 0x80 != 0;
+                self.d = (self.d << 1) | self.df as u8;
+                self.df = carry;
+            }
+            _ => {
+                let value = self.immediate(memory);
+                self.subtract(self.d, value, self.df);
+            }
+        }
+    }
+
+    fn execute_long(&mut self, memory: &[u8; 4096], n: u8) {
+        let rp = self.p as usize;
+        let (branch, condition) = match n {
+            0x0 => (true, true),
+            0x1 => (true, self.q),
+            0x2 => (true, self.d == 0),
+            0x3 => (true, self.df),
+            // NOP
+            0x4 => return,
+            0x5 => (false, !self.q),
+            0x6 => (false, self.d != 0),
+            0x7 => (false, !self.df),
+            0x8 => (false, true),
+            0x9 => (true, !self.q),
+            0xa => (true, self.d != 0),
+            0xb => (true, !self.df),
+            0xc => (false, self.ie),
+            0xd => (false, self.q),
+            0xe => (false, self.d == 0),
+            _ => (false, self.df),
+        };
+
+        match (branch, condition) {
+            (true, true) => {
+                let high = self.read(memory, self.r[rp]);
+                let low = self.read(memory, self.r[rp].wrapping_add(1));
+                self.r[rp] = (high as u16) << 8 | low as u16;
+            }
+            // a branch not taken skips its address just like a taken skip
+            (true, false) | (false, true) => self.r[rp] = self.r[rp].wrapping_add(2),
+            (false, false) => {}
+        }
+    }
+
+    fn execute_fn(&mut self, memory: &mut [u8; 4096], n: u8) {
+        let value = match n {
+            0x6 | 0xe => 0,
+            0x0..=0x7 => self.read(memory, self.r[self.x as usize]),
+            _ => self.immediate(memory),
+        };
+        match n & 0x7 {
+            0x0 => self.d = value,
+            0x1 => self.d |= value,
+            0x2 => self.d &= value,
+            0x3 => self.d ^= value,
+            0x4 => self.add(value, false),
+            0x5 => self.subtract(value, self.d, true),
+            0x6 if n == 0x6 => {
+                self.df = self.d & 1 != 0;
+                self.d >>= 1;
+            }
+            0x6 => {
+                self.df = self.d & 0x80 != 0;
+                self.d <<= 1;
+            }
+            _ => self.subtract(self.d, value, true),
+        }
+    }
+
+    fn add(&mut self, value: u8, carry: bool) {
+        let sum = self.d as u16 + value as u16 + carry as u16;
+        self.d = sum as u8;
+        self.df = sum > 0xff;
+    }
+
+    // DF is set when there is no borrow
+    fn subtract(&mut self, minuend: u8, subtrahend: u8, no_borrow: bool) {
+        let difference = minuend as i16 - subtrahend as i16 - !no_borrow as i16;
+        self.d = difference as u8;
+        self.df = difference >= 0;
+    }
+}
@@ -1,10 +1,13 @@
-use std::collections::VecDeque;
-
-use rand::Rng;
-
 use crate::bit_ops::{get_bit_at, to_u8};
-use crate::quirks::{CH8_QUIRKS, Quirks};
+use crate::cdp1802::Cdp1802;
+use crate::framebuffer::{Framebuffer, Resolution};
+use crate::keys::Keys;
+use crate::palette::Palette;
+use crate::persistence::Persistence;
+use crate::quirks::{CH8_QUIRKS, Platform, Quirks};
 use crate::registers::Registers;
+use crate::rng::{DefaultRng, Rng, RngMode};
+use crate::timing::{TimingMode, vip_cycles, VIP_CYCLES_PER_FRAME, VIP_DISPLAY_CYCLES};
 use crate::to_u16;
 
 const FONT: [u8; 80] = [0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
@@ -24,104 +27,477 @@ const FONT: [u8; 80] = [0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
     0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
     0xF0, 0x80, 0xF0, 0x80, 0x80];  // F
 
+// 8x10 digits of SUPER-CHIP for FX30, A-F as added by Octo
+#[cfg(feature = "schip")]
+const BIG_FONT: [u8; 160] = [0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0]; // F
+
+mod chip8e;
+pub mod chip8x;
+#[cfg(feature = "std")]
+pub mod megachip;
+#[cfg(feature = "schip")]
+mod schip;
+#[cfg(feature = "xochip")]
+pub mod xochip;
+
+use chip8x::ColorBoard;
+
 const FONT_POINTER: u16 = 0x000;
-const PROGRAM_POINTER: u16 = 0x200;
+#[cfg(feature = "schip")]
+const BIG_FONT_POINTER: u16 = 0x050;
+const STACK_SIZE: usize = 16;
+// The 4K of the VIP, XO-CHIP builds make room for its 64K
+const MEMORY_SIZE: usize = if cfg!(feature = "xochip") { 0x10000 } else { 0x1000 };
+const VIP_MEMORY: usize = 0x1000;
+// std builds keep the memory on the heap so a machine is cheap to move, like while loading a save state
+#[cfg(feature = "std")]
+type Memory = Box<[u8; MEMORY_SIZE]>;
+#[cfg(not(feature = "std"))]
+type Memory = [u8; MEMORY_SIZE];
+
+#[cfg(feature = "std")]
+fn empty_memory() -> Memory {
+    Box::new([0; MEMORY_SIZE])
+}
+
+#[cfg(not(feature = "std"))]
+fn empty_memory() -> Memory {
+    [0; MEMORY_SIZE]
+}
+
+// Where the VIP interpreter keeps its state for machine code routines
+const VIP_REGISTERS: usize = 0xef0;
+const VIP_DISPLAY: usize = 0xf00;
+const VIP_STACK: u16 = 0xecf;
+const MAX_ROUTINE_CYCLES: u32 = VIP_CYCLES_PER_FRAME * REFRESH_RATE;
 
-pub const CH8_WIDTH: u8 = 64;
-pub const CH8_HEIGHT: u8 = 32;
+pub const CLOCK_SPEED: u32 = 500;
+pub const REFRESH_RATE: u32 = 60;
+pub const CYCLES_PER_FRAME: u32 = CLOCK_SPEED / REFRESH_RATE;
 
-pub type Chip8Vram = [[bool; CH8_WIDTH as usize]; CH8_HEIGHT as usize];
+pub type DebugSink = fn(u16, &str);
 
-pub struct Chip8 {
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chip8<R: Rng = DefaultRng> {
     registers: Registers,
-    memory: [u8; 4096],
-    stack: VecDeque<u16>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    memory: Memory,
+    stack: [u16; STACK_SIZE],
+    sp: usize,
     delay_timer: u8,
     sound_timer: u8,
     pc: u16,
-    pub vram: Chip8Vram,
+    pub vram: Framebuffer,
     pub vram_changed: bool,
     pub blocked: bool,
     blocked_key_vx: u8,
+    blocked_key_pressed: Option<u8>,
     config: Config,
     pub vblank: VBLank,
+    cycle_budget: i64,
+    cdp1802: Cdp1802,
+    color_board: ColorBoard,
+    delay_wait: bool,
+    exited: bool,
+    // the RPL user flags FX75 and FX85 save registers to
+    #[cfg(feature = "schip")]
+    flags: [u8; 16],
+    #[cfg(feature = "std")]
+    megachip: Option<Box<megachip::MegaChip>>,
+    #[cfg(feature = "xochip")]
+    xochip: xochip::XoChip,
+    rng: R,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Chip8Error {
+    UnknownOpcode(u16),
+    MachineCodeRoutine(u16),
+    MachineCodeTimeout(u16),
+    StackOverflow,
+    StackUnderflow,
+    InvalidAddress(u32),
+    RomTooLarge(usize),
+}
+
+impl core::fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Chip8Error::UnknownOpcode(hex) => write!(f, "{:#06x} not recognized command", hex),
+            Chip8Error::MachineCodeRoutine(hex) => write!(f, "{:#06x} machine code routines are not supported", hex),
+            Chip8Error::MachineCodeTimeout(hex) => write!(f, "{:#06x} machine code routine did not return", hex),
+            Chip8Error::StackOverflow => write!(f, "Stack overflow"),
+            Chip8Error::InvalidAddress(addr) => write!(f, "{:#06x} is outside of memory", addr),
+            Chip8Error::StackUnderflow => write!(f, "Stack underflow"),
+            Chip8Error::RomTooLarge(size) => write!(f, "{} bytes don't fit into memory", size),
+        }
+    }
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VBLank {
     WaitForDraw,
     WaitForInterrupt,
     Free,
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Config {
-    print_debug_messages: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    debug_sink: Option<DebugSink>,
     quirks: Quirks,
+    seed: u32,
+    rng_mode: RngMode,
+    cycles_per_frame: u32,
+    timing: TimingMode,
+    cdp1802: bool,
+    platform: Platform,
+    palette: Palette,
+    persistence: Persistence,
 }
 
 impl Config {
     pub fn new(print_debug_messages: bool, quirks: Quirks) -> Self {
         Config {
-            print_debug_messages,
+            debug_sink: if print_debug_messages { default_debug_sink() } else { None },
             quirks,
+            seed: random_seed(),
+            rng_mode: RngMode::XorShift,
+            cycles_per_frame: CYCLES_PER_FRAME,
+            timing: TimingMode::Instructions,
+            cdp1802: false,
+            platform: Platform::CosmacVip,
+            palette: Palette::default(),
+            persistence: Persistence::Off,
         }
     }
 
     pub fn ch8() -> Self {
         Config::new(false, CH8_QUIRKS)
     }
+
+    pub fn with_debug_sink(mut self, debug_sink: DebugSink) -> Self {
+        self.debug_sink = Some(debug_sink);
+        self
+    }
+
+    pub fn with_quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_rng_mode(mut self, rng_mode: RngMode) -> Self {
+        self.rng_mode = rng_mode;
+        self
+    }
+
+    pub fn with_cycles_per_frame(mut self, cycles_per_frame: u32) -> Self {
+        self.cycles_per_frame = cycles_per_frame;
+        self
+    }
+
+    pub fn with_timing(mut self, timing: TimingMode) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    // Selects the instruction set and the quirks of the platform
+    pub fn with_platform(mut self, platform: Platform) -> Self {
+        self.platform = platform;
+        self.quirks = platform.quirks();
+        self
+    }
+
+    pub fn with_cdp1802(mut self, enabled: bool) -> Self {
+        self.cdp1802 = enabled;
+        self
+    }
+
+    // Colours the frontend draws the pixel values with
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn with_persistence(mut self, persistence: Persistence) -> Self {
+        self.persistence = persistence;
+        self
+    }
+
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn rng_mode(&self) -> RngMode {
+        self.rng_mode
+    }
+
+    pub fn cycles_per_frame(&self) -> u32 {
+        self.cycles_per_frame
+    }
+
+    pub fn timing(&self) -> TimingMode {
+        self.timing
+    }
+
+    pub fn cdp1802(&self) -> bool {
+        self.cdp1802
+    }
+
+    pub fn platform(&self) -> Platform {
+        self.platform
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    pub fn persistence(&self) -> Persistence {
+        self.persistence
+    }
+
+    fn default_rng(&self) -> DefaultRng {
+        DefaultRng::new(self.rng_mode, self.seed)
+    }
+}
+
+#[cfg(feature = "std")]
+fn default_debug_sink() -> Option<DebugSink> {
+    Some(|hex, name| println!("{:#06x} {}", hex, name))
+}
+
+#[cfg(not(feature = "std"))]
+fn default_debug_sink() -> Option<DebugSink> {
+    None
+}
+
+// EX9E and EXA1 only see the main keypad
+fn is_pressed(keys: Keys, vx: u8) -> bool {
+    vx <= 0xf && keys.contains(vx)
+}
+
+#[cfg(feature = "std")]
+fn random_seed() -> u32 {
+    rand::random()
+}
+
+#[cfg(not(feature = "std"))]
+fn random_seed() -> u32 {
+    0
 }
 
 impl Chip8 {
-    pub fn new(rom: Vec<u8>, config: Config) -> Self {
+    pub fn new(rom: &[u8], config: Config) -> Result<Self, Chip8Error> {
+        let rng = config.default_rng();
+        Chip8::with_rng(rom, config, rng)
+    }
+
+    pub fn default(rom: &[u8]) -> Result<Self, Chip8Error> {
+        Chip8::new(rom, Config::ch8())
+    }
+}
+
+impl<R: Rng> Chip8<R> {
+    pub fn with_rng(rom: &[u8], config: Config, rng: R) -> Result<Self, Chip8Error> {
         let mut chip8 = Chip8 {
             registers: Registers::default(),
-            memory: [0; 4096],
-            stack: VecDeque::new(),
+            memory: empty_memory(),
+            stack: [0; STACK_SIZE],
+            sp: 0,
             delay_timer: 0,
             sound_timer: 0,
-            pc: 0x200,
-            vram: [[false; CH8_WIDTH as usize]; CH8_HEIGHT as usize],
+            pc: config.platform.program_start(),
+            vram: Framebuffer::new(Resolution::Lores),
             vram_changed: false,
             blocked: false,
             blocked_key_vx: 0,
+            blocked_key_pressed: None,
             config,
             vblank: VBLank::Free,
+            cycle_budget: 0,
+            cdp1802: Cdp1802::default(),
+            color_board: ColorBoard::new(),
+            delay_wait: false,
+            exited: false,
+            #[cfg(feature = "schip")]
+            flags: [0; 16],
+            #[cfg(feature = "std")]
+            megachip: None,
+            #[cfg(feature = "xochip")]
+            xochip: xochip::XoChip::default(),
+            rng,
         };
 
         chip8.load_to_memory(&FONT, FONT_POINTER);
-        chip8.load_to_memory(rom.as_slice(), PROGRAM_POINTER);
+        #[cfg(feature = "schip")]
+        if chip8.config.platform.has_hires() { chip8.load_to_memory(&BIG_FONT, BIG_FONT_POINTER) }
+        #[cfg(feature = "xochip")]
+        if chip8.config.platform == Platform::XoChip { chip8.vram.set_planes(2) }
+        let space = chip8.config.platform.memory_size() - chip8.pc as usize;
+        chip8.load_to_memory(&rom[..rom.len().min(space)], chip8.pc);
+        // MegaChip roms keep their data past the 4K the interpreter can address, the other platforms can't load more
+        #[cfg(feature = "std")]
+        if chip8.config.platform == Platform::MegaChip {
+            chip8.megachip = Some(Box::new(megachip::MegaChip::new(rom, chip8.pc as usize)));
+            return Ok(chip8);
+        }
+        if rom.len() > space { return Err(Chip8Error::RomTooLarge(rom.len())); }
 
-        chip8
+        Ok(chip8)
     }
 
-    pub fn default(rom: Vec<u8>) -> Self {
-        Chip8::new(rom, Config::ch8())
+    // Jumps and skips can take pc past the end of memory, the fetch stops there
+    fn fetch(&mut self) -> Result<u16, Chip8Error> {
+        let instruction = self.instruction_at(self.pc).ok_or(Chip8Error::InvalidAddress(self.pc as u32))?;
+        self.pc = self.pc.wrapping_add(2);
+        Ok(instruction)
+    }
+
+    // Programs reach past the end of memory through I, that stops them like a bad fetch instead of panicking
+    fn read(&self, addr: usize) -> Result<u8, Chip8Error> {
+        self.memory().get(addr).copied().ok_or(Chip8Error::InvalidAddress(addr as u32))
+    }
+
+    fn write(&mut self, addr: usize, value: u8) -> Result<(), Chip8Error> {
+        let size = self.config.platform.memory_size();
+        let byte = self.memory[..size].get_mut(addr).ok_or(Chip8Error::InvalidAddress(addr as u32))?;
+        *byte = value;
+        Ok(())
     }
 
-    fn fetch(&mut self) -> u16 {
-        let instruction = to_u16!(self.memory[(self.pc) as usize], self.memory[(self.pc + 1) as usize]);
-        self.pc += 2;
-        instruction
+    // The part of the memory the platform has
+    fn memory(&self) -> &[u8] {
+        &self.memory[..self.config.platform.memory_size()]
+    }
+
+    fn instruction_at(&self, addr: u16) -> Option<u16> {
+        let bytes = self.memory().get(addr as usize..addr as usize + 2)?;
+        Some(to_u16!(bytes[0], bytes[1]))
     }
 
     pub fn load_to_memory(&mut self, data: &[u8], start_point: u16) {
-        let mut curr_point = start_point;
-        for byte in data {
-            self.memory[curr_point as usize] = *byte;
-            curr_point += 1
-        }
+        let start = start_point as usize;
+        self.memory[start..start + data.len()].copy_from_slice(data);
     }
 
     pub fn set_pixel(&mut self, y: u8, x: u8, val: bool) {
-        self.vram[y as usize][x as usize] = val;
+        self.vram.set(x as usize, y as usize, val);
     }
 
     pub fn get_pixel(&self, y: u8, x: u8) -> bool {
-        self.vram[y as usize][x as usize]
+        self.vram.get(x as usize, y as usize)
+    }
+
+    pub fn get_register(&self, x: u8) -> u8 {
+        self.registers.get(x)
+    }
+
+    pub fn read_memory(&self, addr: u16) -> Option<u8> {
+        self.read(addr as usize).ok()
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn i(&self) -> u16 {
+        self.registers.i
+    }
+
+    // 00FD stopped the program
+    pub fn exited(&self) -> bool {
+        self.exited
+    }
+
+    pub fn is_self_jump(&self) -> bool {
+        self.instruction_at(self.pc) == Some(0x1000 | self.pc)
+    }
+
+    // Platforms with sound of their own, the others make no sound yet
+    #[cfg(feature = "std")]
+    pub fn has_audio(&self) -> bool {
+        matches!(self.config.platform, Platform::MegaChip | Platform::XoChip)
+    }
+
+    // Fills out with 8 bit unsigned samples at the given output rate
+    #[cfg(feature = "std")]
+    pub fn fill_audio(&mut self, out: &mut [u8], output_rate: u32) {
+        match self.config.platform {
+            Platform::XoChip => self.fill_xochip_audio(out, output_rate),
+            _ => self.fill_megachip_audio(out, output_rate),
+        }
+    }
+
+    pub fn vram_hash(&self) -> u64 {
+        self.vram.hash()
+    }
+
+    pub fn color_board(&self) -> &ColorBoard {
+        &self.color_board
     }
 
-    pub fn execute_next_opcode(&mut self, key: Option<u8>) {
-        let hex = self.fetch();
+    pub fn rng(&self) -> &R {
+        &self.rng
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    // Refills the cycle budget, an instruction that ran over the previous budget is paid from the new one
+    pub fn start_frame(&mut self) {
+        let budget = match self.config.timing {
+            TimingMode::Instructions => self.config.cycles_per_frame,
+            TimingMode::CosmacVip => VIP_CYCLES_PER_FRAME - VIP_DISPLAY_CYCLES,
+        };
+        self.cycle_budget = self.cycle_budget.min(0) + budget as i64;
+    }
+
+    pub fn frame_done(&self) -> bool {
+        self.cycle_budget <= 0
+    }
+
+    fn charge(&mut self, hex: u16) {
+        self.cycle_budget -= match self.config.timing {
+            TimingMode::Instructions => 1,
+            TimingMode::CosmacVip => {
+                // waiting for the display interrupt uses up the rest of the frame
+                let wait = if self.config.quirks.waits_for_interrupt(hex) { self.cycle_budget.max(0) } else { 0 };
+                wait + vip_cycles(hex, &self.registers) as i64
+            }
+        };
+    }
+
+    pub fn execute_next_opcode(&mut self, keys: Keys) -> Result<(), Chip8Error> {
+        let hex = self.fetch()?;
+        self.charge(hex);
 
         let nibbles = (
             ((hex & 0xF000) >> 12_u8) as u8,
@@ -129,12 +505,23 @@ impl Chip8 {
             ((hex & 0x00F0) >> 4_u8) as u8,
             (hex & 0x000F) as u8
         );
+        let handled = match self.config.platform {
+            Platform::Chip8X => self.execute_chip8x(hex, nibbles, keys),
+            Platform::Chip8E => self.execute_chip8e(hex, nibbles),
+            #[cfg(feature = "std")]
+            Platform::MegaChip => self.execute_megachip(hex, nibbles)?,
+            #[cfg(feature = "xochip")]
+            Platform::XoChip => self.execute_xochip(hex, nibbles)?,
+            _ => false,
+        } || self.execute_hires(hex, nibbles);
+        if handled { return Ok(()); }
+
         match nibbles {
             (0x0, 0x0, 0xe, 0x0) => self.clear_display(hex),
-            (0x0, 0x0, 0xe, 0xe) => self.return_sub(hex),
-            (0x0, _, _, _) => panic!("UNHANDLED COMMAND - MACHINED CODE ROUTINE"),
+            (0x0, 0x0, 0xe, 0xe) => self.return_sub(hex)?,
+            (0x0, _, _, _) => self.machine_code_routine(hex, keys)?,
             (0x1, n1, n2, n3) => self.jump(hex, n1, n2, n3),
-            (0x2, n1, n2, n3) => self.call(hex, n1, n2, n3),
+            (0x2, n1, n2, n3) => self.call(hex, n1, n2, n3)?,
             (0x3, x, n1, n2) => self.skip_if_equal(hex, x, n1, n2),
             (0x4, x, n1, n2) => self.skip_if_not_equal(hex, x, n1, n2),
             (0x5, x, y, 0x0) => self.skip_if_registers_equal(hex, x, y),
@@ -153,61 +540,146 @@ impl Chip8 {
             (0xa, n1, n2, n3) => self.set_i(hex, n1, n2, n3),
             (0xb, n1, n2, n3) => self.jump_plus_v0(hex, n1, n2, n3),
             (0xc, x, n1, n2) => self.set_vx_to_rand_and_nn(hex, x, n1, n2),
-            (0xd, x, y, n) => self.draw(hex, x, y, n),
-            (0xe, x, 0x9, 0xe) => self.skip_if_pressed(hex, x, key),
-            (0xe, x, 0xa, 0x1) => self.skip_if_not_pressed(hex, x, key),
+            (0xd, x, y, n) => self.draw(hex, x, y, n)?,
+            (0xe, x, 0x9, 0xe) => self.skip_if_pressed(hex, x, keys),
+            (0xe, x, 0xa, 0x1) => self.skip_if_not_pressed(hex, x, keys),
             (0xf, x, 0x0, 0x7) => self.set_vx_to_delay(hex, x),
             (0xf, x, 0x0, 0xa) => self.get_key(hex, x),
             (0xf, x, 0x1, 0x5) => self.set_delay_timer(hex, x),
             (0xf, x, 0x1, 0x8) => self.set_sound_timer(hex, x),
             (0xf, x, 0x1, 0xe) => self.add_vx_to_i(hex, x),
             (0xf, x, 0x2, 0x9) => self.set_i_to_sprite(hex, x),
-            (0xf, x, 0x3, 0x3) => self.binary_coded_decimal(hex, x),
-            (0xf, x, 0x5, 0x5) => self.reg_dump(hex, x),
-            (0xf, x, 0x6, 0x5) => self.reg_load(hex, x),
-            _ => panic!("{:#06x} {hex} not recognized command", hex)
+            (0xf, x, 0x3, 0x3) => self.binary_coded_decimal(hex, x)?,
+            (0xf, x, 0x5, 0x5) => self.reg_dump(hex, x)?,
+            (0xf, x, 0x6, 0x5) => self.reg_load(hex, x)?,
+            _ => return Err(Chip8Error::UnknownOpcode(hex))
         }
+
+        Ok(())
+    }
+
+    // SUPER-CHIP instructions shared by the platforms with the 128x64 screen
+    #[cfg(feature = "schip")]
+    fn execute_hires(&mut self, hex: u16, nibbles: (u8, u8, u8, u8)) -> bool {
+        self.config.platform.has_hires() && self.execute_schip(hex, nibbles)
+    }
+
+    #[cfg(not(feature = "schip"))]
+    fn execute_hires(&mut self, _hex: u16, _nibbles: (u8, u8, u8, u8)) -> bool {
+        false
     }
 
     fn skip(&mut self) {
-        self.pc += 2;
+        self.pc = self.pc.wrapping_add(self.instruction_size(self.pc));
+    }
+
+    #[cfg(not(feature = "xochip"))]
+    fn instruction_size(&self, _addr: u16) -> u16 {
+        2
+    }
+
+    #[cfg(not(feature = "xochip"))]
+    fn selected_planes(&self) -> u8 {
+        u8::MAX
     }
 
     fn clear_display(&mut self, hex: u16) {
+        if self.config.timing == TimingMode::Instructions && self.config.quirks.clear_wait(&mut self.vblank) {
+            self.pc -= 2;
+            return;
+        }
+
         self.print_debug_message(hex, "Clear Display");
-        self.vram = [[false; CH8_WIDTH as usize]; CH8_HEIGHT as usize];
+        self.vram.clear_planes(self.selected_planes());
         self.vram_changed = true;
     }
 
-    pub fn draw(&mut self, hex: u16, x: u8, y: u8, n: u8) {
-        if self.config.quirks.display_wait(&mut self.vblank) {
+    pub fn draw(&mut self, hex: u16, x: u8, y: u8, n: u8) -> Result<(), Chip8Error> {
+        if self.config.timing == TimingMode::Instructions && self.config.quirks.display_wait(&mut self.vblank) {
             self.pc -= 2;
-            return;
+            return Ok(());
         }
 
         self.print_debug_message(hex, "Draw");
-        let vx = self.registers.get(x) & 63;
-        let vy = self.registers.get(y) & 31;
+        let resolution = self.vram.resolution();
+        let vx = self.registers.get(x) as usize % resolution.width();
+        let vy = self.registers.get(y) as usize % resolution.height();
         let mut flipped = false;
+        let (width, height) = match n {
+            0 if resolution == Resolution::Hires => (16, 16),
+            0 => self.config.quirks.lores_big_sprite().unwrap_or((8, 0)),
+            n => (8, n),
+        };
+        let bytes_per_row = width as usize / 8;
+        // every selected plane takes the next sprite from memory
+        let mut sprite = self.registers.i as usize;
+        let selected = self.selected_planes();
+
+        for plane in (0..self.vram.planes()).map(|plane| 1 << plane).filter(|plane| selected & plane != 0) {
+            for row in 0..height {
+                for pix in 0..width {
+                    let i_val = self.read(sprite + row as usize * bytes_per_row + pix as usize / 8)?;
+                    let i_bit = get_bit_at(i_val, 7 - pix % 8);
+                    let (curr_x, curr_y) = self.config.quirks.clipping(vx + pix as usize, vy + row as usize, resolution);
+
+                    if i_bit && curr_x < resolution.width() && curr_y < resolution.height() {
+                        if self.vram.toggle(curr_x, curr_y, plane) { flipped = true };
+                        self.vram_changed = true;
+                    }
+                }
+            }
+            sprite += height as usize * bytes_per_row;
+        }
 
-        for row in 0..n {
-            for pix in 0..8 {
-                let i_val = self.memory[self.registers.i as usize + row as usize];
-                let i_bit = get_bit_at(i_val, 7 - pix);
-                let (curr_x, curr_y) = self.config.quirks.clipping(vx, pix, vy, row);
+        self.registers.set_vf(flipped as u8);
+        Ok(())
+    }
 
-                if i_bit && curr_x < CH8_WIDTH && curr_y < CH8_HEIGHT {
-                    let current_pixel = self.get_pixel(curr_y, curr_x);
-                    let screen_val = i_bit ^ current_pixel;
-                    if !screen_val && current_pixel { flipped = true };
+    fn machine_code_routine(&mut self, hex: u16, keys: Keys) -> Result<(), Chip8Error> {
+        // the VIP display page only holds the 64x32 screen
+        if !self.config.cdp1802 || self.vram.resolution() != Resolution::Lores {
+            return Err(Chip8Error::MachineCodeRoutine(hex));
+        }
+        self.print_debug_message(hex, "Machine code routine");
 
-                    self.set_pixel(curr_y, curr_x, screen_val);
-                    self.vram_changed = true;
-                }
+        // the VIP interpreter keeps V0-VF at 0xEF0 and the screen at 0xF00, routines read and change them there,
+        // so both are copied in before the call and back after it. Without the 1802 nothing above is reached.
+        for x in 0..16 {
+            self.memory[VIP_REGISTERS + x] = self.registers.get(x);
+        }
+        for (row, pixels) in self.vram.rows().enumerate() {
+            for (byte, chunk) in pixels.chunks(8).enumerate() {
+                self.memory[VIP_DISPLAY + row * 8 + byte] = chunk.iter().fold(0, |bits, pixel| bits << 1 | (pixel & 1));
             }
         }
+        self.cdp1802.set_register(0x2, VIP_STACK);
+        self.cdp1802.set_register(0x5, self.pc);
+        self.cdp1802.set_register(0xa, self.registers.i);
+        self.cdp1802.set_register(0xb, VIP_DISPLAY as u16);
+
+        let vip_memory = self.memory.first_chunk_mut::<VIP_MEMORY>().expect("the memory holds at least the 4K of the VIP");
+        let cycles = self.cdp1802.call(vip_memory, hex & 0x0fff, keys, MAX_ROUTINE_CYCLES)
+            .ok_or(Chip8Error::MachineCodeTimeout(hex))?;
+        if self.config.timing == TimingMode::CosmacVip { self.cycle_budget -= cycles as i64 }
+
+        for x in 0..16 {
+            self.registers.set(x, self.memory[VIP_REGISTERS + x]);
+        }
+        self.registers.i = self.cdp1802.register(0xa) & 0x0fff;
+        self.pc = self.cdp1802.register(0x5) & 0x0fff;
 
-        self.registers.set_vf(flipped as u8);
+        let mut vram = self.vram.clone();
+        for y in 0..vram.height() {
+            for x in 0..vram.width() {
+                vram.set(x, y, get_bit_at(self.memory[VIP_DISPLAY + y * 8 + x / 8], 7 - (x % 8) as u8));
+            }
+        }
+        if vram != self.vram {
+            self.vram = vram;
+            self.vram_changed = true;
+        }
+
+        Ok(())
     }
 
     fn set_i(&mut self, hex: u16, n1: u8, n2: u8, n3: u8) {
@@ -342,89 +814,91 @@ impl Chip8 {
 
     fn add_vx_to_i(&mut self, hex: u16, x: u8) {
         self.print_debug_message(hex, "Sets I += Vx");
-        self.registers.i += self.registers.get(x) as u16;
+        self.registers.i = self.registers.i.wrapping_add(self.registers.get(x) as u16);
+        self.config.quirks.i_overflow(&mut self.registers);
     }
 
-    fn call(&mut self, hex: u16, n1: u8, n2: u8, n3: u8) {
+    fn call(&mut self, hex: u16, n1: u8, n2: u8, n3: u8) -> Result<(), Chip8Error> {
         self.print_debug_message(hex, "Sets sub");
         let addr = to_u16!(n1, n2, n3);
-        self.stack.push_front(self.pc);
+        if self.sp == STACK_SIZE { return Err(Chip8Error::StackOverflow); }
+        self.stack[self.sp] = self.pc;
+        self.sp += 1;
         self.pc = addr;
+        Ok(())
     }
 
-    fn return_sub(&mut self, hex: u16) {
+    fn return_sub(&mut self, hex: u16) -> Result<(), Chip8Error> {
         self.print_debug_message(hex, "Return sub");
-        let addr = self.stack.pop_front().unwrap();
-        self.pc = addr;
+        self.sp = self.sp.checked_sub(1).ok_or(Chip8Error::StackUnderflow)?;
+        self.pc = self.stack[self.sp];
+        Ok(())
     }
 
-    fn skip_if_pressed(&mut self, hex: u16, x: u8, key: Option<u8>) {
+    fn skip_if_pressed(&mut self, hex: u16, x: u8, keys: Keys) {
         self.print_debug_message(hex, "Skip if key == Vx");
-        if let Some(k) = key {
-            if k == self.registers.get(x) {
-                self.skip();
-            }
+        if is_pressed(keys, self.registers.get(x)) {
+            self.skip();
         }
     }
 
-    fn skip_if_not_pressed(&mut self, hex: u16, x: u8, key: Option<u8>) {
+    fn skip_if_not_pressed(&mut self, hex: u16, x: u8, keys: Keys) {
         self.print_debug_message(hex, "Skip if key != Vx");
-        if let Some(k) = key {
-            if k == self.registers.get(x) {
-                return;
-            }
+        if !is_pressed(keys, self.registers.get(x)) {
+            self.skip();
         }
-        self.skip();
     }
 
-    fn binary_coded_decimal(&mut self, hex: u16, x: u8) {
+    fn binary_coded_decimal(&mut self, hex: u16, x: u8) -> Result<(), Chip8Error> {
         self.print_debug_message(hex, "Binary coded decimal");
 
         let mut curr_val = self.registers.get(x);
         let binary_hundred = curr_val / 100;
-        self.memory[self.registers.i as usize] = binary_hundred;
+        self.write(self.registers.i as usize, binary_hundred)?;
         curr_val -= binary_hundred * 100;
 
         let binary_tens = curr_val / 10;
-        self.memory[(self.registers.i + 1) as usize] = binary_tens;
+        self.write(self.registers.i as usize + 1, binary_tens)?;
         curr_val -= binary_tens * 10;
 
-        self.memory[(self.registers.i + 2) as usize] = curr_val;
+        self.write(self.registers.i as usize + 2, curr_val)
     }
 
-    fn reg_dump(&mut self, hex: u16, x: u8) {
+    fn reg_dump(&mut self, hex: u16, x: u8) -> Result<(), Chip8Error> {
         self.print_debug_message(hex, "Reg dump");
         for n in 0..=x {
-            self.memory[(self.registers.i + n as u16) as usize] = self.registers.get(n);
+            self.write(self.registers.i as usize + n as usize, self.registers.get(n))?;
         }
         self.config.quirks.memory(&mut self.registers, x as u16);
+        Ok(())
     }
 
-    fn reg_load(&mut self, hex: u16, x: u8) {
+    fn reg_load(&mut self, hex: u16, x: u8) -> Result<(), Chip8Error> {
         self.print_debug_message(hex, "Reg load");
         for n in 0..=x {
-            self.registers.set(n, self.memory[(self.registers.i + n as u16) as usize]);
+            self.registers.set(n, self.read(self.registers.i as usize + n as usize)?);
         }
         self.config.quirks.memory(&mut self.registers, x as u16);
+        Ok(())
     }
 
     fn set_vx_to_rand_and_nn(&mut self, hex: u16, x: u8, n1: u8, n2: u8) {
         self.print_debug_message(hex, "Set VX to rand() & NN");
-        let val = to_u8(n1, n2) as u16;
-        let mut rng = rand::thread_rng();
-        self.registers.set(x, (rng.gen_range(0..256) & val) as u8);
+        let val = to_u8(n1, n2);
+        self.registers.set(x, self.rng.next_u8() & val);
     }
 
     fn get_key(&mut self, hex: u16, x: u8) {
         self.print_debug_message(hex, "Waiting for key");
         self.blocked_key_vx = x;
+        self.blocked_key_pressed = None;
         self.blocked = true;
     }
 
     fn set_i_to_sprite(&mut self, hex: u16, x: u8) {
         self.print_debug_message(hex, "Set I to value of sprite at Vx");
         let character = self.registers.get(x);
-        self.registers.i = FONT_POINTER + (character * 5) as u16;
+        self.registers.i = FONT_POINTER + (character & 0xf) as u16 * 5;
     }
 
     pub fn set_key(&mut self, key: u8) {
@@ -432,6 +906,22 @@ impl Chip8 {
         self.blocked = false;
     }
 
+    // FX0A only continues once the pressed key is released again
+    pub fn update_blocked_key(&mut self, keys: Keys) {
+        // only the main keypad can answer FX0A, the lowest held key is taken
+        match self.blocked_key_pressed {
+            None => self.blocked_key_pressed = (0..=0xf).find(|k| keys.contains(*k)),
+            Some(k) if !keys.contains(k) => self.set_key(k),
+            Some(_) => {}
+        }
+
+        // the VIP polls the keypad until the next interrupt
+        self.cycle_budget -= match self.config.timing {
+            TimingMode::Instructions => 1,
+            TimingMode::CosmacVip => self.cycle_budget.max(1),
+        };
+    }
+
     fn decrement_delay_timer(&mut self) {
         self.delay_timer = self.delay_timer.saturating_sub(1)
     }
@@ -450,6 +940,19 @@ impl Chip8 {
     }
 
     fn print_debug_message(&self, hex: u16, name: &str) {
-        if self.config.print_debug_messages { println!("{:#06x} {}", hex, name) }
+        if let Some(sink) = self.config.debug_sink { sink(hex, name) }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "std")]
+impl<R: Rng + serde::Serialize + serde::de::DeserializeOwned> Chip8<R> {
+    // The whole machine with its rng as JSON, a loaded state continues exactly where the saved one stopped
+    pub fn save_state(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|err| err.to_string())
+    }
+
+    // The debug sink isn't part of the state, the loaded machine runs without one
+    pub fn load_state(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|err| format!("Invalid save state: {}", err))
+    }
+}
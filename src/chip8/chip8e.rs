@@ -0,0 +1,69 @@
+use crate::chip8::Chip8;
+use crate::rng::Rng;
+
+impl<R: Rng> Chip8<R> {
+    pub(super) fn execute_chip8e(&mut self, hex: u16, nibbles: (u8, u8, u8, u8)) -> bool {
+        match nibbles {
+            (0x0, 0x0, 0xe, 0xd) => {
+                self.print_debug_message(hex, "Stop");
+                self.pc -= 2;
+            }
+            (0x0, 0x0, 0xf, 0x2) => self.print_debug_message(hex, "No operation"),
+            (0x0, 0x1, 0x5, 0x1) => {
+                self.print_debug_message(hex, "Wait for delay == 0");
+                if self.delay_timer != 0 { self.pc -= 2 }
+            }
+            (0x0, 0x1, 0x8, 0x8) => {
+                self.print_debug_message(hex, "Skip");
+                self.skip();
+            }
+            (0x5, x, y, 0x1) => {
+                self.print_debug_message(hex, "Skip if Vx > Vy");
+                if self.registers.get(x) > self.registers.get(y) { self.skip() }
+            }
+            (0x5, x, y, 0x2) => {
+                self.print_debug_message(hex, "Store Vx..Vy at I");
+                for r in x..=y {
+                    self.memory[self.registers.i as usize & 0xfff] = self.registers.get(r);
+                    self.registers.i += 1;
+                }
+            }
+            (0x5, x, y, 0x3) => {
+                self.print_debug_message(hex, "Load Vx..Vy from I");
+                for r in x..=y {
+                    self.registers.set(r, self.memory[self.registers.i as usize & 0xfff]);
+                    self.registers.i += 1;
+                }
+            }
+            (0xb, 0xb, n1, n2) => {
+                self.print_debug_message(hex, "Branch back NN bytes");
+                // branches leaving memory are reported by the next fetch
+                self.pc = self.pc.wrapping_sub((n1 << 4 | n2) as u16);
+            }
+            (0xb, 0xf, n1, n2) => {
+                self.print_debug_message(hex, "Branch forward NN bytes");
+                self.pc = self.pc.wrapping_add((n1 << 4 | n2) as u16);
+            }
+            (0xf, _, 0x0, 0x3) => self.print_debug_message(hex, "Output Vx to port 3"),
+            (0xf, x, 0x1, 0xb) => {
+                self.print_debug_message(hex, "Skip Vx bytes");
+                self.pc = self.pc.wrapping_add(self.registers.get(x) as u16);
+            }
+            (0xf, x, 0x4, 0xf) => {
+                self.print_debug_message(hex, "Sets delay = Vx and wait for delay == 0");
+                if !self.delay_wait {
+                    self.delay_timer = self.registers.get(x);
+                    self.delay_wait = true;
+                }
+                if self.delay_timer != 0 { self.pc -= 2 } else { self.delay_wait = false }
+            }
+            // nothing is connected to the input port
+            (0xf, x, 0xe, 0x3 | 0x7) => {
+                self.print_debug_message(hex, "Sets Vx = input port 3");
+                self.registers.set(x, 0);
+            }
+            _ => return false,
+        }
+        true
+    }
+}
@@ -0,0 +1,110 @@
+use crate::chip8::Chip8;
+use crate::framebuffer::Resolution;
+use crate::keys::Keys;
+use crate::rng::Rng;
+
+// VP-590 colours in the order of their 3 bit codes
+pub const CHIP8X_COLORS: [[u8; 3]; 8] = [
+    [0x00, 0x00, 0x00], // black
+    [0xff, 0x00, 0x00], // red
+    [0x00, 0x00, 0xff], // blue
+    [0xff, 0x00, 0xff], // violet
+    [0x00, 0xff, 0x00], // green
+    [0xff, 0xff, 0x00], // yellow
+    [0x00, 0xff, 0xff], // aqua
+    [0xff, 0xff, 0xff], // white
+];
+// 02A0 steps through blue, black, green and red
+const BACKGROUNDS: [u8; 4] = [2, 0, 4, 1];
+const ZONE_WIDTH: usize = 8;
+const ZONES: usize = 8;
+const ROWS: usize = Resolution::Lores.height();
+const DEFAULT_FOREGROUND: u8 = 1;
+
+// Foreground colour of every 8x1 pixel zone and the background colour of the whole screen
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColorBoard {
+    zones: [[u8; ZONES]; ROWS],
+    background: usize,
+}
+
+impl ColorBoard {
+    pub fn new() -> Self {
+        ColorBoard { zones: [[DEFAULT_FOREGROUND; ZONES]; ROWS], background: 0 }
+    }
+
+    pub fn color(&self, x: usize, y: usize, pixel: bool) -> [u8; 3] {
+        let code = if pixel { self.zones[y][x / ZONE_WIDTH] } else { BACKGROUNDS[self.background] };
+        CHIP8X_COLORS[code as usize]
+    }
+
+    fn cycle_background(&mut self) {
+        self.background = (self.background + 1) % BACKGROUNDS.len();
+    }
+
+    fn fill(&mut self, columns: impl Iterator<Item=usize> + Clone, rows: impl Iterator<Item=usize>, color: u8) {
+        for row in rows {
+            for column in columns.clone() {
+                self.zones[row % ROWS][column % ZONES] = color;
+            }
+        }
+    }
+}
+
+impl Default for ColorBoard {
+    fn default() -> Self {
+        ColorBoard::new()
+    }
+}
+
+const KEYPAD_2: u8 = 0x10;
+
+impl<R: Rng> Chip8<R> {
+    pub(super) fn execute_chip8x(&mut self, hex: u16, nibbles: (u8, u8, u8, u8), keys: Keys) -> bool {
+        match nibbles {
+            (0x0, 0x2, 0xa, 0x0) => {
+                self.print_debug_message(hex, "Cycle background colour");
+                self.color_board.cycle_background();
+                self.vram_changed = true;
+            }
+            (0x5, x, y, 0x1) => {
+                self.print_debug_message(hex, "Add Vy to Vx per nibble");
+                let sum = (self.registers.get(x) & 0x77) + (self.registers.get(y) & 0x77);
+                self.registers.set(x, sum & 0x77);
+            }
+            (0xb, x, y, 0x0) => {
+                // Vx holds the first column and the column count - 1 of 8 pixel blocks, Vx + 1 the same for 4 row blocks
+                self.print_debug_message(hex, "Set colour of blocks");
+                let (horizontal, vertical) = (self.registers.get(x), self.registers.get((x + 1) & 0xf));
+                let columns = (horizontal & 0xf) as usize..=((horizontal & 0xf) + (horizontal >> 4)) as usize;
+                let rows = (vertical & 0xf) as usize * 4..((vertical & 0xf) + (vertical >> 4) + 1) as usize * 4;
+                self.color_board.fill(columns, rows, self.registers.get(y) & 0x7);
+                self.vram_changed = true;
+            }
+            (0xb, x, y, n) => {
+                self.print_debug_message(hex, "Set colour of sprite area");
+                let (vx, vy) = (self.registers.get(x) as usize, self.registers.get((x + 1) & 0xf) as usize);
+                let columns = vx / ZONE_WIDTH..=vx / ZONE_WIDTH;
+                self.color_board.fill(columns, vy..vy + n as usize, self.registers.get(y) & 0x7);
+                self.vram_changed = true;
+            }
+            (0xe, x, 0xf, 0x2) => {
+                self.print_debug_message(hex, "Skip if key == Vx on keypad 2");
+                if keys.contains(KEYPAD_2 | (self.registers.get(x) & 0xf)) { self.skip() }
+            }
+            (0xe, x, 0xf, 0x5) => {
+                self.print_debug_message(hex, "Skip if key != Vx on keypad 2");
+                if !keys.contains(KEYPAD_2 | (self.registers.get(x) & 0xf)) { self.skip() }
+            }
+            (0xf, _, 0xf, 0x8) => self.print_debug_message(hex, "Sets tone = Vx"),
+            // nothing is connected to the input port
+            (0xf, x, 0xf, 0xb) => {
+                self.print_debug_message(hex, "Sets Vx = input port");
+                self.registers.set(x, 0);
+            }
+            _ => return false,
+        }
+        true
+    }
+}
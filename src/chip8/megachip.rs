@@ -0,0 +1,269 @@
+use crate::bit_ops::to_u8;
+use crate::chip8::{Chip8, Chip8Error, VIP_MEMORY};
+use crate::framebuffer::{Framebuffer, Resolution};
+use crate::rng::Rng;
+
+pub const MEGA_WIDTH: usize = Resolution::Mega.width();
+pub const MEGA_HEIGHT: usize = Resolution::Mega.height();
+// Roms bigger than this grow the memory, I can address 24 bits
+const MIN_MEMORY: usize = 0x10000;
+// Sample rate (2 bytes), length (3 bytes) and a padding byte precede the 8 bit unsigned samples
+const SAMPLE_HEADER: u32 = 6;
+const SILENCE: u8 = 0x80;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Blend {
+    Normal,
+    Alpha25,
+    Alpha50,
+    Alpha75,
+    Add,
+    Multiply,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sample {
+    pub start: u32,
+    pub length: u32,
+    pub rate: u16,
+    pub looping: bool,
+    position: f64,
+}
+
+// State of the MegaChip extension, sprites are indexed colours drawn into a back buffer that 00E0 presents to the
+// framebuffer in its Mega resolution
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MegaChip {
+    enabled: bool,
+    memory: Vec<u8>,
+    long_i: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    palette: [u32; 256],
+    sprite_width: usize,
+    sprite_height: usize,
+    alpha: u8,
+    blend: Blend,
+    collision_color: u8,
+    back: Vec<u32>,
+    indices: Vec<u8>,
+    sample: Option<Sample>,
+}
+
+impl MegaChip {
+    pub(crate) fn new(rom: &[u8], start: usize) -> Self {
+        let mut memory = vec![0; MIN_MEMORY.max(start + rom.len())];
+        memory[start..start + rom.len()].copy_from_slice(rom);
+
+        MegaChip {
+            enabled: false,
+            memory,
+            long_i: None,
+            palette: [0; 256],
+            sprite_width: 0,
+            sprite_height: 0,
+            alpha: 0xff,
+            blend: Blend::Normal,
+            collision_color: 0,
+            back: vec![0; MEGA_WIDTH * MEGA_HEIGHT],
+            indices: vec![0; MEGA_WIDTH * MEGA_HEIGHT],
+            sample: None,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn palette(&self) -> &[u32; 256] {
+        &self.palette
+    }
+
+    pub fn alpha(&self) -> u8 {
+        self.alpha
+    }
+
+    pub fn blend(&self) -> Blend {
+        self.blend
+    }
+
+    pub fn sample(&self) -> Option<&Sample> {
+        self.sample.as_ref()
+    }
+
+    // The low 4K are shared with the interpreter memory so code can modify data there, everything reads through here
+    fn get(&self, base: &[u8], addr: u32) -> Option<u8> {
+        match addr as usize {
+            addr if addr < base.len() => Some(base[addr]),
+            addr => self.memory.get(addr).copied(),
+        }
+    }
+
+    fn read(&self, base: &[u8], addr: u32) -> u8 {
+        self.get(base, addr).unwrap_or(0)
+    }
+
+    fn draw(&mut self, base: &[u8], i: u32, x: usize, y: usize) -> bool {
+        let mut collision = false;
+        for row in 0..self.sprite_height {
+            for column in 0..self.sprite_width {
+                let index = self.read(base, i + (row * self.sprite_width + column) as u32);
+                let (px, py) = (x + column, y + row);
+                if index == 0 || px >= MEGA_WIDTH || py >= MEGA_HEIGHT { continue; }
+
+                let pixel = py * MEGA_WIDTH + px;
+                if self.indices[pixel] != 0 && self.indices[pixel] == self.collision_color { collision = true }
+                self.indices[pixel] = index;
+                self.back[pixel] = blend(self.back[pixel], self.palette[index as usize], self.blend);
+            }
+        }
+        collision
+    }
+
+    // The screen alpha and the alpha of every colour fade it towards black
+    fn present(&mut self, vram: &mut Framebuffer) {
+        for (index, argb) in self.back.iter().enumerate() {
+            let [a, r, g, b] = argb.to_be_bytes();
+            let alpha = a as u32 * self.alpha as u32;
+            let fade = |channel: u8| (channel as u32 * alpha / (0xff * 0xff)) as u8;
+            vram.set_color(index % MEGA_WIDTH, index / MEGA_WIDTH, [fade(r), fade(g), fade(b), 0xff]);
+        }
+        self.back.fill(0);
+        self.indices.fill(0);
+    }
+}
+
+fn blend(dst: u32, src: u32, mode: Blend) -> u32 {
+    let channels = |f: &dyn Fn(u32, u32) -> u32| {
+        (0..4).fold(0, |pixel, channel| {
+            let shift = channel * 8;
+            pixel | f((dst >> shift) & 0xff, (src >> shift) & 0xff).min(0xff) << shift
+        })
+    };
+    let mix = |amount: u32| channels(&|d, s| (d * (256 - amount) + s * amount) >> 8);
+
+    match mode {
+        Blend::Normal => src,
+        Blend::Alpha25 => mix(64),
+        Blend::Alpha50 => mix(128),
+        Blend::Alpha75 => mix(192),
+        Blend::Add => channels(&|d, s| d + s),
+        Blend::Multiply => channels(&|d, s| d * s / 0xff),
+    }
+}
+
+impl<R: Rng> Chip8<R> {
+    pub fn megachip(&self) -> Option<&MegaChip> {
+        self.megachip.as_deref()
+    }
+
+    // Fills out with 8 bit unsigned samples of the playing sound at the given output rate
+    pub fn fill_megachip_audio(&mut self, out: &mut [u8], output_rate: u32) {
+        let Some(mega) = self.megachip.as_deref_mut() else { return out.fill(SILENCE) };
+        for value in out.iter_mut() {
+            let addr = match &mut mega.sample {
+                Some(sample) if (sample.position as u32) < sample.length => {
+                    let addr = sample.start + SAMPLE_HEADER + sample.position as u32;
+                    sample.position += sample.rate as f64 / output_rate as f64;
+                    if sample.looping && sample.position as u32 >= sample.length { sample.position = 0.0 }
+                    addr
+                }
+                _ => { *value = SILENCE; continue; }
+            };
+            *value = mega.get(&self.memory[..VIP_MEMORY], addr).unwrap_or(SILENCE);
+        }
+    }
+
+    pub(super) fn execute_megachip(&mut self, hex: u16, nibbles: (u8, u8, u8, u8)) -> Result<bool, Chip8Error> {
+        let Some(mut mega) = self.megachip.take() else { return Ok(false) };
+        let handled = self.execute_megachip_with(&mut mega, hex, nibbles);
+        self.megachip = Some(mega);
+        handled
+    }
+
+    fn execute_megachip_with(&mut self, mega: &mut MegaChip, hex: u16, nibbles: (u8, u8, u8, u8)) -> Result<bool, Chip8Error> {
+        let i = mega.long_i.unwrap_or(self.registers.i as u32);
+
+        match nibbles {
+            (0x0, 0x0, 0x1, 0x0) => {
+                self.print_debug_message(hex, "Disable megachip mode");
+                mega.enabled = false;
+                self.set_resolution(Resolution::Lores);
+            }
+            (0x0, 0x0, 0x1, 0x1) => {
+                self.print_debug_message(hex, "Enable megachip mode");
+                mega.enabled = true;
+                self.set_resolution(Resolution::Mega);
+            }
+            (0x0, 0x0, 0xe, 0x0) if mega.enabled => {
+                self.print_debug_message(hex, "Present and clear megachip screen");
+                mega.present(&mut self.vram);
+                self.vram_changed = true;
+            }
+            (0x0, 0x1, n1, n2) => {
+                let low = self.fetch()?;
+                self.print_debug_message(hex, "Sets I = NNNNNN");
+                let addr = (to_u8(n1, n2) as u32) << 16 | low as u32;
+                mega.long_i = Some(addr);
+                self.registers.i = addr as u16 & 0x0fff;
+            }
+            (0x0, 0x2, n1, n2) => {
+                self.print_debug_message(hex, "Load palette from I");
+                for color in 0..to_u8(n1, n2) as u32 {
+                    let argb = (0..4).fold(0, |argb, byte| argb << 8 | mega.read(self.memory(), i + color * 4 + byte) as u32);
+                    mega.palette[color as usize + 1] = argb;
+                }
+            }
+            (0x0, 0x3, n1, n2) => {
+                self.print_debug_message(hex, "Set sprite width");
+                mega.sprite_width = match to_u8(n1, n2) { 0 => 256, width => width as usize };
+            }
+            (0x0, 0x4, n1, n2) => {
+                self.print_debug_message(hex, "Set sprite height");
+                mega.sprite_height = match to_u8(n1, n2) { 0 => 256, height => height as usize };
+            }
+            (0x0, 0x5, n1, n2) => {
+                self.print_debug_message(hex, "Set screen alpha");
+                mega.alpha = to_u8(n1, n2);
+            }
+            (0x0, 0x6, 0x0, n) => {
+                self.print_debug_message(hex, "Play sample at I");
+                let header = |offset: u32| mega.read(self.memory(), i + offset) as u32;
+                mega.sample = Some(Sample {
+                    start: i,
+                    length: header(2) << 16 | header(3) << 8 | header(4),
+                    rate: (header(0) << 8 | header(1)) as u16,
+                    looping: n == 0,
+                    position: 0.0,
+                });
+            }
+            (0x0, 0x7, 0x0, 0x0) => {
+                self.print_debug_message(hex, "Stop sample");
+                mega.sample = None;
+            }
+            (0x0, 0x8, 0x0, n @ 0x0..=0x5) => {
+                self.print_debug_message(hex, "Set blend mode");
+                mega.blend = [Blend::Normal, Blend::Alpha25, Blend::Alpha50, Blend::Alpha75, Blend::Add, Blend::Multiply][n as usize];
+            }
+            (0x0, 0x9, n1, n2) => {
+                self.print_debug_message(hex, "Set collision colour");
+                mega.collision_color = to_u8(n1, n2);
+            }
+            (0xd, x, y, _) if mega.enabled => {
+                self.print_debug_message(hex, "Draw megachip sprite");
+                let (vx, vy) = (self.registers.get(x) as usize, self.registers.get(y) as usize);
+                let collision = mega.draw(self.memory(), i, vx, vy);
+                self.registers.set_vf(collision as u8);
+            }
+            // the other instructions setting I go back to 12 bit addresses
+            (0xa, _, _, _) | (0xf, _, 0x1, 0xe) | (0xf, _, 0x2, 0x9) | (0xf, _, 0x3, 0x0) => {
+                mega.long_i = None;
+                return Ok(false);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,70 @@
+use crate::chip8::{BIG_FONT_POINTER, Chip8};
+use crate::framebuffer::Resolution;
+use crate::rng::Rng;
+
+impl<R: Rng> Chip8<R> {
+    pub(super) fn execute_schip(&mut self, hex: u16, nibbles: (u8, u8, u8, u8)) -> bool {
+        match nibbles {
+            (0x0, 0x0, 0xc, n) => {
+                self.print_debug_message(hex, "Scroll down");
+                self.scroll(0, n as isize);
+            }
+            (0x0, 0x0, 0xf, 0xb) => {
+                self.print_debug_message(hex, "Scroll right");
+                self.scroll(4, 0);
+            }
+            (0x0, 0x0, 0xf, 0xc) => {
+                self.print_debug_message(hex, "Scroll left");
+                self.scroll(-4, 0);
+            }
+            (0x0, 0x0, 0xf, 0xd) => {
+                // stays on 00FD so the program stops where it exited
+                self.print_debug_message(hex, "Exit");
+                self.exited = true;
+                self.pc -= 2;
+            }
+            (0x0, 0x0, 0xf, 0xe) => {
+                self.print_debug_message(hex, "Low resolution");
+                self.set_resolution(Resolution::Lores);
+            }
+            (0x0, 0x0, 0xf, 0xf) => {
+                self.print_debug_message(hex, "High resolution");
+                self.set_resolution(Resolution::Hires);
+            }
+            (0xf, x, 0x3, 0x0) => {
+                self.print_debug_message(hex, "Set I to big sprite at Vx");
+                self.registers.i = BIG_FONT_POINTER + (self.registers.get(x) & 0xf) as u16 * 10;
+            }
+            (0xf, x, 0x7, 0x5) => {
+                self.print_debug_message(hex, "Save V0-Vx to flags");
+                for n in 0..=x {
+                    self.flags[n as usize] = self.registers.get(n);
+                }
+            }
+            (0xf, x, 0x8, 0x5) => {
+                self.print_debug_message(hex, "Load V0-Vx from flags");
+                for n in 0..=x {
+                    self.registers.set(n, self.flags[n as usize]);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    // Distances are in hires pixels, SCHIP 1.1 halves them in lores instead of doubling the pixels
+    pub(super) fn scroll(&mut self, dx: isize, dy: isize) {
+        let (dx, dy) = match self.vram.resolution() {
+            Resolution::Lores if self.config.quirks.half_pixel_scroll() => (dx / 2, dy / 2),
+            _ => (dx, dy),
+        };
+        self.vram.scroll_planes(dx, dy, self.selected_planes());
+        self.vram_changed = true;
+    }
+
+    // The screen is cleared on every switch
+    pub(super) fn set_resolution(&mut self, resolution: Resolution) {
+        self.vram.set_resolution(resolution);
+        self.vram_changed = true;
+    }
+}
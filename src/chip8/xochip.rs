@@ -0,0 +1,134 @@
+#[cfg(feature = "std")]
+use crate::bit_ops::get_bit_at;
+use crate::chip8::{Chip8, Chip8Error};
+use crate::quirks::Platform;
+use crate::rng::Rng;
+
+#[cfg(feature = "std")]
+const PATTERN_BITS: f64 = 128.0;
+// The pattern plays at 4000 * 2^((pitch - 64) / 48) bits per second
+#[cfg(feature = "std")]
+const BASE_RATE: f64 = 4000.0;
+#[cfg(feature = "std")]
+const SILENCE: u8 = 0x80;
+#[cfg(feature = "std")]
+const LEVELS: [u8; 2] = [0x40, 0xc0];
+
+// Sound of XO-CHIP, a 1 bit pattern of 128 samples loops while the sound timer runs
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct XoChip {
+    pattern: [u8; 16],
+    pitch: u8,
+    // bit mask of the planes FN01 selected
+    planes: u8,
+    #[cfg(feature = "std")]
+    position: f64,
+}
+
+impl Default for XoChip {
+    fn default() -> Self {
+        XoChip {
+            pattern: [0; 16],
+            pitch: 64,
+            planes: 1,
+            #[cfg(feature = "std")]
+            position: 0.0,
+        }
+    }
+}
+
+impl XoChip {
+    pub fn pattern(&self) -> &[u8; 16] {
+        &self.pattern
+    }
+
+    pub fn pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    pub fn planes(&self) -> u8 {
+        self.planes
+    }
+}
+
+impl<R: Rng> Chip8<R> {
+    pub fn xochip(&self) -> &XoChip {
+        &self.xochip
+    }
+
+    // Fills out with 8 bit unsigned samples of the pattern at the given output rate
+    #[cfg(feature = "std")]
+    pub fn fill_xochip_audio(&mut self, out: &mut [u8], output_rate: u32) {
+        let step = BASE_RATE * 2f64.powf((self.xochip.pitch as f64 - 64.0) / 48.0) / output_rate as f64;
+        for value in out.iter_mut() {
+            if self.sound_timer == 0 {
+                *value = SILENCE;
+                continue;
+            }
+            let bit = self.xochip.position as usize;
+            *value = LEVELS[get_bit_at(self.xochip.pattern[bit / 8], 7 - (bit % 8) as u8) as usize];
+            self.xochip.position = (self.xochip.position + step) % PATTERN_BITS;
+        }
+    }
+
+    // F000 NNNN takes two words, skips jump over both
+    pub(super) fn instruction_size(&self, addr: u16) -> u16 {
+        let long_load = self.config.platform == Platform::XoChip && self.instruction_at(addr) == Some(0xf000);
+        if long_load { 4 } else { 2 }
+    }
+
+    // FN01 picks the planes drawing, clearing and scrolling act on, the other platforms use all of them
+    pub(super) fn selected_planes(&self) -> u8 {
+        if self.config.platform == Platform::XoChip { self.xochip.planes } else { u8::MAX }
+    }
+
+    pub(super) fn execute_xochip(&mut self, hex: u16, nibbles: (u8, u8, u8, u8)) -> Result<bool, Chip8Error> {
+        match nibbles {
+            (0x0, 0x0, 0xd, n) => {
+                self.print_debug_message(hex, "Scroll up");
+                self.scroll(0, -(n as isize));
+            }
+            (0x5, x, y, 0x2) => {
+                self.print_debug_message(hex, "Save Vx-Vy to I");
+                for (offset, n) in register_range(x, y).enumerate() {
+                    self.write(self.registers.i as usize + offset, self.registers.get(n))?;
+                }
+            }
+            (0x5, x, y, 0x3) => {
+                self.print_debug_message(hex, "Load Vx-Vy from I");
+                for (offset, n) in register_range(x, y).enumerate() {
+                    let value = self.read(self.registers.i as usize + offset)?;
+                    self.registers.set(n, value);
+                }
+            }
+            (0xf, 0x0, 0x0, 0x0) => {
+                let addr = self.fetch()?;
+                self.print_debug_message(hex, "Sets I = NNNN");
+                self.registers.i = addr;
+            }
+            (0xf, 0x0, 0x0, 0x2) => {
+                self.print_debug_message(hex, "Load audio pattern from I");
+                for n in 0..self.xochip.pattern.len() {
+                    self.xochip.pattern[n] = self.read(self.registers.i as usize + n)?;
+                }
+            }
+            (0xf, n, 0x0, 0x1) => {
+                self.print_debug_message(hex, "Select planes");
+                self.xochip.planes = n;
+            }
+            (0xf, x, 0x3, 0xa) => {
+                self.print_debug_message(hex, "Set pitch to Vx");
+                self.xochip.pitch = self.registers.get(x);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+// Vx to Vy in either direction
+fn register_range(x: u8, y: u8) -> impl Iterator<Item=u8> {
+    let (len, down) = (x.abs_diff(y), y < x);
+    (0..=len).map(move |n| if down { x - n } else { x + n })
+}
@@ -1,78 +1,433 @@
-use std::{thread, time};
+#[cfg(feature = "sdl")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "sdl")]
+use std::thread;
 
-use crate::chip8::Chip8;
-use crate::input::Input;
-use crate::sdl_driver::SDLDriver;
+use crate::chip8::{Chip8, Chip8Error};
+#[cfg(feature = "sdl")]
+use crate::audio::Audio;
+use crate::chip8::REFRESH_RATE;
+#[cfg(feature = "sdl")]
+use crate::cli::Options;
+#[cfg(feature = "sdl")]
+use crate::controller::{ControllerMap, MAX_CONTROLLERS};
+#[cfg(feature = "sdl")]
+use crate::hotkeys::Command;
+#[cfg(feature = "sdl")]
+use crate::input::{Input, MenuKey};
+#[cfg(feature = "sdl")]
+use crate::keymap::Keymap;
+#[cfg(feature = "std")]
+use crate::input_script::InputScript;
+use crate::keys::Keys;
+#[cfg(feature = "std")]
+use crate::movie::Movie;
+#[cfg(feature = "sdl")]
+use crate::movie::Session;
+use crate::rng::Rng;
+#[cfg(feature = "sdl")]
+use crate::osd::{Menu, Osd};
+#[cfg(feature = "sdl")]
+use crate::quirks::PLATFORMS;
+#[cfg(feature = "sdl")]
+use crate::rom_database::rom_hash;
+#[cfg(feature = "sdl")]
+use crate::settings::state_path;
+#[cfg(feature = "sdl")]
+use crate::sdl_driver::{open_window, SDLDriver};
 
-const CLOCK_SPEED: u32 = 500;
-const REFRESH_RATE: u32 = 60;
-const CYCLES_PER_FRAME: u32 = CLOCK_SPEED / REFRESH_RATE;
+#[cfg(feature = "sdl")]
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+// Slow motion runs one frame out of this many
+#[cfg(feature = "sdl")]
+const SLOW_MOTION_DIVISOR: u64 = 4;
 
-pub fn run_with_sdl(chip8: &mut Chip8, scale: u32) {
-    let mut sdl_driver = SDLDriver::new(scale).unwrap();
-    let mut input = Input::new(&sdl_driver.sdl_context);
+#[cfg(feature = "sdl")]
+pub fn run_with_sdl<R: Rng + Clone + serde::Serialize + serde::de::DeserializeOwned>(chip8: &mut Chip8<R>, rom: &[u8], options: &Options, keymap: &Keymap, controllers: &[ControllerMap; MAX_CONTROLLERS], session: &mut Session) -> Result<(), String> {
+    let sdl_context = sdl2::init()?;
+    let canvas = open_window(&sdl_context, options.window, *chip8.config().palette())?;
+    let texture_creator = canvas.texture_creator();
+    let mut sdl_driver = SDLDriver::new(canvas, &texture_creator, options.window, *chip8.config().palette(), chip8.config().persistence());
+    let mut input = Input::new(&sdl_context, &options.hotkeys, keymap, controllers)?;
 
-    let sleep_time = time::Duration::from_millis(((1.0 / REFRESH_RATE as f64) * 1000.0) as u64);
+    // opened once a platform with sound runs
+    let mut audio: Option<Audio> = None;
+
+    let initial = chip8.clone();
+    let mut osd = Osd::new();
+    let mut stats = (Instant::now(), 0, 0);
+    let (mut paused, mut advance) = (false, false);
+    let mut tick: u64 = 0;
+
+    let frame_time = Duration::from_millis(((1.0 / REFRESH_RATE as f64) * 1000.0) as u64);
 
     loop {
-        for _ in 0..CYCLES_PER_FRAME {
-            let key = input.poll_keys();
-            if chip8.blocked {
-                if let Some(k) = key { chip8.set_key(k) }
-                continue;
+        let started = Instant::now();
+        let keys = input.poll_keys();
+        if input.quit {
+            return Ok(());
+        }
+
+        for command in input.commands.drain(..) {
+            match command {
+                Command::NextTheme => osd.message(format!("Theme {}", sdl_driver.next_theme().name())),
+                Command::ToggleFullscreen => sdl_driver.toggle_fullscreen()?,
+                Command::ToggleGrid => osd.message(format!("Grid {}", on_off(sdl_driver.toggle_grid()))),
+                Command::ScaleUp => osd.message(format!("Scale {}", sdl_driver.change_scale(1)?)),
+                Command::ScaleDown => osd.message(format!("Scale {}", sdl_driver.change_scale(-1)?)),
+                Command::ToggleRegisters => osd.show_registers = !osd.show_registers,
+                Command::ToggleStats => osd.show_stats = !osd.show_stats,
+                Command::Pause => paused = !paused,
+                Command::FrameAdvance => (paused, advance) = (true, true),
+                Command::SlowMotion => osd.slow_motion = !osd.slow_motion,
+                Command::Reset => {
+                    *chip8 = initial.clone();
+                    session.restart(rom, chip8.config());
+                    sdl_driver.invalidate();
+                    osd.message("Reset");
+                }
+                Command::HardReset => {
+                    let items = std::iter::once("current settings").chain(PLATFORMS.iter().map(|platform| platform.name()));
+                    osd.menu = Some(Menu::new("Hard reset as", items.map(str::to_string).collect()));
+                    input.menu_open = true;
+                }
+                Command::SaveState => osd.message(match save_state(chip8, rom) {
+                    Ok(()) => "State saved".to_string(),
+                    Err(err) => err,
+                }),
+                // a movie only holds keys, jumping to another state would break it
+                Command::LoadState if !matches!(session, Session::Live) => osd.message("States can't be loaded into a movie"),
+                Command::LoadState => match load_state(rom) {
+                    Ok(state) => {
+                        *chip8 = state;
+                        sdl_driver.invalidate();
+                        osd.message("State loaded");
+                    }
+                    Err(err) => osd.message(err),
+                },
+                Command::Quit => return Ok(()),
+                Command::FastForward => {}
+                Command::Redraw => sdl_driver.invalidate(),
             }
+        }
 
-            chip8.execute_next_opcode(key);
+        for message in input.messages.drain(..) {
+            osd.message(message);
         }
 
+        for menu_key in input.menu_keys.drain(..) {
+            let Some(menu) = &mut osd.menu else { break };
+            match menu_key {
+                MenuKey::Up => menu.up(),
+                MenuKey::Down => menu.down(),
+                MenuKey::Cancel => osd.menu = None,
+                MenuKey::Select => {
+                    // a fresh machine with the rom loaded again, the first item keeps the config
+                    let config = match menu.selected {
+                        0 => initial.config().clone(),
+                        n => initial.config().clone().with_platform(PLATFORMS[n - 1]),
+                    };
+                    let platform = config.platform().name();
+                    osd.menu = None;
+                    match Chip8::with_rng(rom, config, initial.rng().clone()) {
+                        Ok(fresh) => {
+                            *chip8 = fresh;
+                            session.restart(rom, chip8.config());
+                            sdl_driver.invalidate();
+                            osd.message(format!("Hard reset as {}", platform));
+                        }
+                        Err(err) => osd.message(format!("Can't run as {}: {}", platform, err)),
+                    }
+                }
+            }
+        }
+        input.menu_open = osd.menu.is_some();
+        osd.paused = paused;
+        osd.fast_forward = input.fast_forward && !paused;
 
-        chip8.handle_vblank();
+        let uncapped = osd.fast_forward && options.fast_forward == 0;
+        let frames = match () {
+            _ if paused || osd.menu.is_some() => std::mem::take(&mut advance) as u32,
+            _ if osd.fast_forward => options.fast_forward.max(1),
+            _ if osd.slow_motion => tick.is_multiple_of(SLOW_MOTION_DIVISOR) as u32,
+            _ => 1,
+        };
 
-        if chip8.vram_changed {
-            sdl_driver.draw(chip8);
-            chip8.vram_changed = false;
+        let mut frame = 0;
+        while frame < frames || (uncapped && started.elapsed() < frame_time) {
+            let instructions = run_frame(chip8, session.next_keys(keys)).map_err(|err| err.to_string())?;
+            stats = (stats.0, stats.1 + 1, stats.2 + instructions);
+            frame += 1;
+        }
+        match (chip8.has_audio(), &mut audio) {
+            (true, Some(audio)) if frame > 0 => audio.update(chip8)?,
+            (true, None) => audio = Some(Audio::new(&sdl_context)?),
+            (false, Some(_)) => audio = None,
+            _ => {}
+        }
+        if stats.0.elapsed() >= STATS_INTERVAL {
+            osd.set_stats(stats.1, stats.2);
+            stats = (Instant::now(), 0, 0);
         }
 
-        //TODO better way than sleeping, calculate target_instructions and executed_instructions
-        // if executed_cycles == cycles_per_frame {
-        //     let elapsed_time = now.elapsed();
-        // }
-        thread::sleep(sleep_time);
+        sdl_driver.draw(chip8, &osd)?;
+        chip8.vram_changed = false;
+        osd.tick();
+        tick += 1;
 
-        //TODO fix timers
-        //TODO sound support
-        chip8.decrement_timers();
+        thread::sleep(frame_time.saturating_sub(started.elapsed()));
     }
 }
 
-pub fn run_stop_on_blocked(chip8: &mut Chip8) {
-    loop {
-        for _ in 0..CYCLES_PER_FRAME {
-            chip8.execute_next_opcode(None);
+#[cfg(feature = "sdl")]
+fn save_state<R: Rng + serde::Serialize + serde::de::DeserializeOwned>(chip8: &Chip8<R>, rom: &[u8]) -> Result<(), String> {
+    let path = state_path(&rom_hash(rom)).ok_or("No directory for save states")?;
+    if let Some(dir) = path.parent() { fs::create_dir_all(dir).map_err(|err| err.to_string())? }
+    fs::write(&path, chip8.save_state()?).map_err(|err| format!("Can't save state to {}: {}", path.display(), err))
+}
 
-            if chip8.blocked {
-                return;
-            }
-        }
+#[cfg(feature = "sdl")]
+fn load_state<R: Rng + serde::Serialize + serde::de::DeserializeOwned>(rom: &[u8]) -> Result<Chip8<R>, String> {
+    let path = state_path(&rom_hash(rom)).ok_or("No directory for save states")?;
+    let json = fs::read_to_string(&path).map_err(|_| "No saved state".to_string())?;
+    Chip8::load_state(&json)
+}
 
-        chip8.handle_vblank();
-        chip8.decrement_timers();
+// Returns the number of instructions executed, waiting for a key doesn't count
+pub fn run_frame<R: Rng>(chip8: &mut Chip8<R>, keys: Keys) -> Result<u32, Chip8Error> {
+    let mut instructions = 0;
+    chip8.start_frame();
+    while !chip8.frame_done() {
+        if !chip8.blocked { instructions += 1 }
+        step(chip8, keys)?;
     }
+
+    end_frame(chip8);
+    Ok(instructions)
 }
 
-pub fn run_for_cycles(chip8: &mut Chip8, cycles: u16) {
-    let mut elapsed_cycles = 0;
-    loop {
-        for _ in 0..CYCLES_PER_FRAME {
-            chip8.execute_next_opcode(None);
+#[cfg(feature = "sdl")]
+fn on_off(value: bool) -> &'static str {
+    if value { "on" } else { "off" }
+}
+
+pub fn step<R: Rng>(chip8: &mut Chip8<R>, keys: Keys) -> Result<(), Chip8Error> {
+    if chip8.blocked {
+        chip8.update_blocked_key(keys);
+        return Ok(());
+    }
+
+    chip8.execute_next_opcode(keys)
+}
+
+pub fn end_frame<R: Rng>(chip8: &mut Chip8<R>) {
+    chip8.handle_vblank();
+    chip8.decrement_timers();
+}
+
+#[cfg(feature = "std")]
+pub fn replay_movie<R: Rng>(chip8: &mut Chip8<R>, movie: &Movie) -> Result<(), Chip8Error> {
+    for keys in movie.frames() {
+        run_frame(chip8, *keys)?;
+    }
+
+    Ok(())
+}
+
+pub fn run_stop_on_blocked<R: Rng>(chip8: &mut Chip8<R>) -> RunSummary {
+    HeadlessRunner::new().stop_on_blocked().run(chip8)
+}
+
+pub fn run_for_cycles<R: Rng>(chip8: &mut Chip8<R>, cycles: u64) -> RunSummary {
+    HeadlessRunner::new().max_cycles(cycles).run(chip8)
+}
+
+// Ten minutes of emulated time, unless max_frames sets another limit
+pub const DEFAULT_MAX_FRAMES: u64 = 10 * 60 * REFRESH_RATE as u64;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StopReason {
+    Cycles,
+    Frames,
+    PcReached(u16),
+    SelfJump(u16),
+    Blocked,
+    MemoryMatched(u16, u8),
+    VramMatched(u64),
+    // the program ended itself with 00FD
+    Exited,
+    #[cfg(feature = "std")]
+    Timeout,
+    Error(Chip8Error),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RunSummary {
+    pub reason: StopReason,
+    pub cycles: u64,
+    pub frames: u64,
+    pub blocked_cycles: u64,
+    #[cfg(feature = "std")]
+    pub elapsed: Duration,
+}
+
+#[derive(Clone, Default)]
+pub struct HeadlessRunner {
+    max_cycles: Option<u64>,
+    max_frames: Option<u64>,
+    stop_at_pc: Option<u16>,
+    stop_on_self_jump: bool,
+    stop_on_blocked: bool,
+    memory_match: Option<(u16, u8)>,
+    vram_hash: Option<u64>,
+    #[cfg(feature = "std")]
+    timeout: Option<Duration>,
+    #[cfg(feature = "std")]
+    script: Option<InputScript>,
+}
+
+impl HeadlessRunner {
+    pub fn new() -> Self {
+        HeadlessRunner::default()
+    }
+
+    pub fn max_cycles(mut self, cycles: u64) -> Self {
+        self.max_cycles = Some(cycles);
+        self
+    }
+
+    pub fn max_frames(mut self, frames: u64) -> Self {
+        self.max_frames = Some(frames);
+        self
+    }
+
+    pub fn stop_at_pc(mut self, addr: u16) -> Self {
+        self.stop_at_pc = Some(addr);
+        self
+    }
+
+    pub fn stop_on_self_jump(mut self) -> Self {
+        self.stop_on_self_jump = true;
+        self
+    }
+
+    pub fn stop_on_blocked(mut self) -> Self {
+        self.stop_on_blocked = true;
+        self
+    }
+
+    pub fn stop_on_memory(mut self, addr: u16, val: u8) -> Self {
+        self.memory_match = Some((addr, val));
+        self
+    }
+
+    pub fn stop_on_vram_hash(mut self, hash: u64) -> Self {
+        self.vram_hash = Some(hash);
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn script(mut self, script: InputScript) -> Self {
+        self.script = Some(script);
+        self
+    }
+
+    pub fn run<R: Rng>(&mut self, chip8: &mut Chip8<R>) -> RunSummary {
+        #[cfg(feature = "std")]
+        let start = Instant::now();
+        let mut cycles = 0;
+        let mut frames = 0;
+        let mut blocked_cycles = 0;
+
+        let reason = 'run: loop {
+            chip8.start_frame();
+            while !chip8.frame_done() {
+                if let Some(reason) = self.check_before_step(chip8) {
+                    break 'run reason;
+                }
+
+                if chip8.blocked { blocked_cycles += 1 }
+                if let Err(err) = step(chip8, self.keys_at(frames, cycles)) {
+                    break 'run StopReason::Error(err);
+                }
+                cycles += 1;
+
+                if let Some(reason) = self.check_after_step(chip8, cycles) {
+                    break 'run reason;
+                }
+            }
+
+            end_frame(chip8);
+            frames += 1;
+
+            if frames >= self.max_frames.unwrap_or(DEFAULT_MAX_FRAMES) {
+                break StopReason::Frames;
+            }
 
-            elapsed_cycles += 1;
-            if elapsed_cycles == cycles {
-                return;
+            #[cfg(feature = "std")]
+            if self.timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+                break StopReason::Timeout;
             }
+        };
+
+        RunSummary {
+            reason,
+            cycles,
+            frames,
+            blocked_cycles,
+            #[cfg(feature = "std")]
+            elapsed: start.elapsed(),
         }
+    }
+
+    fn check_before_step<R: Rng>(&self, chip8: &Chip8<R>) -> Option<StopReason> {
+        if self.stop_at_pc == Some(chip8.pc()) {
+            return Some(StopReason::PcReached(chip8.pc()));
+        }
+        if self.stop_on_self_jump && chip8.is_self_jump() {
+            return Some(StopReason::SelfJump(chip8.pc()));
+        }
+        None
+    }
+
+    fn check_after_step<R: Rng>(&self, chip8: &mut Chip8<R>, cycles: u64) -> Option<StopReason> {
+        if chip8.exited() {
+            return Some(StopReason::Exited);
+        }
+        if self.stop_on_blocked && chip8.blocked {
+            return Some(StopReason::Blocked);
+        }
+        if let Some((addr, val)) = self.memory_match.filter(|(addr, val)| chip8.read_memory(*addr) == Some(*val)) {
+            return Some(StopReason::MemoryMatched(addr, val));
+        }
+        if let (Some(hash), true) = (self.vram_hash, chip8.vram_changed) {
+            // nothing else presents the frame while running headless, so only hash after a change
+            chip8.vram_changed = false;
+            if chip8.vram_hash() == hash {
+                return Some(StopReason::VramMatched(hash));
+            }
+        }
+        if self.max_cycles.is_some_and(|max| cycles >= max) {
+            return Some(StopReason::Cycles);
+        }
+        None
+    }
+
+    #[cfg(feature = "std")]
+    fn keys_at(&mut self, frame: u64, cycle: u64) -> Keys {
+        self.script.as_mut().map_or(Keys::NONE, |script| script.keys_at(frame, cycle))
+    }
 
-        chip8.handle_vblank();
-        chip8.decrement_timers();
+    #[cfg(not(feature = "std"))]
+    fn keys_at(&mut self, _frame: u64, _cycle: u64) -> Keys {
+        Keys::NONE
     }
 }
\ No newline at end of file
@@ -1,17 +1,247 @@
 use std::env;
+use std::path::PathBuf;
+
+use crate::chip8::Config;
+use crate::controller::{ControllerMap, MAX_CONTROLLERS};
+use crate::download::Downloader;
+use crate::hotkeys::Hotkeys;
+use crate::keymap::{Keymap, PRESETS};
+use crate::palette::{Palette, parse_color, Theme};
+use crate::persistence::Persistence;
+use crate::render::{MAX_SCALE, WindowSettings};
+use crate::rom_loader::load_source;
+use crate::quirks::{Platform, Quirks};
+use crate::settings::Settings;
+use crate::timing::TimingMode;
+
+const DEFAULT_FAST_FORWARD: u32 = 4;
+
+pub struct Options {
+    pub rom_path: String,
+    pub record_path: Option<String>,
+    pub replay_path: Option<String>,
+    pub database_path: Option<String>,
+    // Expected sha1 of a rom downloaded from an url
+    pub sha1: Option<String>,
+    pub cache_dir: Option<String>,
+    pub platform: Option<Platform>,
+    pub tickrate: Option<u32>,
+    pub quirks: Option<String>,
+    pub timing: Option<TimingMode>,
+    pub cdp1802: Option<bool>,
+    pub palette: Option<Palette>,
+    pub foreground: Option<[u8; 3]>,
+    pub background: Option<[u8; 3]>,
+    pub persistence: Option<Persistence>,
+    pub window: WindowSettings,
+    pub hotkeys: Hotkeys,
+    pub keymap: Keymap,
+    // Bound on top of the keymap and the keys of the rom database
+    pub keys: Keymap,
+    // Bound on top of the default controller bindings and the keys of the rom database
+    pub controllers: [ControllerMap; MAX_CONTROLLERS],
+    // Frames run per frame while fast forwarding, 0 runs as many as fit in the frame time
+    pub fast_forward: u32,
+}
+
+impl Options {
+    pub fn apply_overrides(&self, mut config: Config) -> Config {
+        if let Some(platform) = self.platform { config = config.with_platform(platform) }
+        if let Some(tickrate) = self.tickrate { config = config.with_cycles_per_frame(tickrate) }
+        if let Some(timing) = self.timing { config = config.with_timing(timing) }
+        if let Some(enabled) = self.cdp1802 { config = config.with_cdp1802(enabled) }
+        if let Some(list) = &self.quirks {
+            let mut quirks = config.quirks().clone();
+            if quirks.apply(list).is_ok() { config = config.with_quirks(quirks) }
+        }
+        if let Some(palette) = self.palette { config = config.with_palette(palette) }
+        if let Some(color) = self.foreground {
+            let palette = config.palette().with_foreground(color);
+            config = config.with_palette(palette);
+        }
+        if let Some(color) = self.background {
+            let palette = config.palette().with_background(color);
+            config = config.with_palette(palette);
+        }
+        if let Some(persistence) = self.persistence { config = config.with_persistence(persistence) }
+        config
+    }
+
+    pub fn downloader(&self) -> Downloader {
+        let downloader = Downloader::new().with_sha1(self.sha1.clone());
+        match &self.cache_dir {
+            Some(dir) => downloader.with_cache_dir(Some(PathBuf::from(dir))),
+            None => downloader,
+        }
+    }
+
+    pub fn keymap(&self, rom_keys: &[(String, u8)]) -> Result<Keymap, String> {
+        let mut keymap = self.keymap.clone();
+        keymap.bind_actions(rom_keys)?;
+        for (host, key) in self.keys.bindings() { keymap.bind(host, *key)? }
+        Ok(keymap)
+    }
+
+    pub fn controller_maps(&self, rom_keys: &[(String, u8)]) -> Result<[ControllerMap; MAX_CONTROLLERS], String> {
+        let mut maps = [ControllerMap::player(0), ControllerMap::player(1)];
+        for (player, (map, user)) in maps.iter_mut().zip(&self.controllers).enumerate() {
+            map.bind_actions(rom_keys, player)?;
+            for (control, key) in user.bindings() { map.bind(control, *key)? }
+        }
+        Ok(maps)
+    }
+}
 
 pub fn load_from_cli() -> Vec<u8> {
-    load_rom_from_path(parse_file_path().as_str())
+    load_rom_from_path(parse_options().rom_path.as_str())
+}
+
+// The config file comes first so the command line overrides it
+pub fn parse_options() -> Options {
+    let args: Vec<String> = env::args().skip(1).collect();
+    Settings::find(&args)
+        .and_then(|settings| parse_args(settings.to_args().into_iter().chain(args)))
+        .unwrap_or_else(|err| panic!("{}", err))
 }
 
-pub fn parse_file_path() -> String {
-    let mut args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        panic!("Please provide valid filepath.")
+pub fn parse_args(mut args: impl Iterator<Item=String>) -> Result<Options, String> {
+    let mut rom_path = None;
+    let mut record_path = None;
+    let mut replay_path = None;
+    let mut database_path = None;
+    let mut sha1 = None;
+    let mut cache_dir = None;
+    let mut platform = None;
+    let mut tickrate = None;
+    let mut quirks = None;
+    let mut timing = None;
+    let mut cdp1802 = None;
+    let mut palette = None;
+    let mut foreground = None;
+    let mut background = None;
+    let mut persistence = None;
+    let mut window = WindowSettings::default();
+    let mut hotkeys = Hotkeys::default();
+    let mut keymap = Keymap::default();
+    let mut keys = Keymap::new();
+    let mut controllers = [ControllerMap::new(), ControllerMap::new()];
+    let mut fast_forward = DEFAULT_FAST_FORWARD;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--record" => record_path = Some(args.next().ok_or("--record requires a path")?),
+            "--replay" => replay_path = Some(args.next().ok_or("--replay requires a path")?),
+            // read by Settings::find before the arguments get here
+            "--config" => { args.next().ok_or("--config requires a path")?; }
+            "--database" => database_path = Some(args.next().ok_or("--database requires a path")?),
+            "--sha1" => {
+                let hash = args.next().ok_or("--sha1 requires a hash")?;
+                if hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit()) { return Err(format!("Invalid sha1 {}", hash)); }
+                sha1 = Some(hash.to_lowercase());
+            }
+            "--cache-dir" => cache_dir = Some(args.next().ok_or("--cache-dir requires a path")?),
+            "--platform" => {
+                let name = args.next().ok_or("--platform requires chip8, chip8x, chip8e, chip48, schip1.0, schip1.1, schip, megachip or xochip")?;
+                platform = Some(name.parse().map_err(|_| format!("Unknown platform {}", name))?);
+            }
+            "--tickrate" => {
+                let value = args.next().ok_or("--tickrate requires a number")?;
+                // nothing would ever run at 0 instructions per frame
+                tickrate = Some(value.parse().ok().filter(|tickrate| *tickrate > 0).ok_or(format!("Invalid tickrate {}", value))?);
+            }
+            "--quirks" => {
+                let list = args.next().ok_or("--quirks requires a list like vf_reset,-shifting")?;
+                list.parse::<Quirks>().map_err(|err| format!("{} in --quirks {}", err, list))?;
+                quirks = Some(list);
+            }
+            "--timing" => timing = match args.next().as_deref() {
+                Some("instructions") => Some(TimingMode::Instructions),
+                Some("vip") => Some(TimingMode::CosmacVip),
+                _ => return Err("--timing requires instructions or vip".to_string()),
+            },
+            "--cdp1802" | "--no-cdp1802" => cdp1802 = Some(arg == "--cdp1802"),
+            "--theme" => {
+                let name = args.next().ok_or("--theme requires classic, amber, green, lcd or octo")?;
+                palette = Some(name.parse::<Theme>().map_err(|_| format!("Unknown theme {}", name))?.palette());
+            }
+            "--palette" => {
+                let list = args.next().ok_or("--palette requires a list like #000000,#ffffff")?;
+                palette = Some(list.parse().map_err(|err| format!("{} in --palette {}", err, list))?);
+            }
+            "--foreground" | "--background" => {
+                let value = args.next().ok_or(format!("{} requires a colour like #ffb000", arg))?;
+                let color = Some(parse_color(&value).map_err(|err| format!("{} in {} {}", err, arg, value))?);
+                if arg == "--foreground" { foreground = color } else { background = color }
+            }
+            "--persistence" => {
+                let mode = args.next().ok_or("--persistence requires off, deflicker, phosphor or phosphor:<percent>")?;
+                persistence = Some(mode.parse().map_err(|_| format!("Unknown persistence {}", mode))?);
+            }
+            "--scale" => {
+                let value = args.next().ok_or("--scale requires a number")?;
+                window.scale = match value.parse() {
+                    Ok(scale @ 1..=MAX_SCALE) => scale,
+                    _ => return Err(format!("Invalid scale {}, it must be between 1 and {}", value, MAX_SCALE)),
+                };
+            }
+            "--hotkeys" => {
+                let list = args.next().ok_or("--hotkeys requires a list like pause=P,reset=Backspace")?;
+                hotkeys.apply(&list)?;
+            }
+            "--keymap" => {
+                let value = args.next().ok_or(format!("--keymap requires {} or a list like 5=Up,8=Down", PRESETS.join(", ")))?;
+                keymap = value.parse().map_err(|err| format!("{} in --keymap {}", err, value))?;
+            }
+            "--keys" => {
+                let list = args.next().ok_or("--keys requires a list like 5=Up,8=Down")?;
+                keys.apply(&list)?;
+            }
+            "--controller" | "--controller2" => {
+                let list = args.next().ok_or(format!("{} requires a list like 5=dpup,6=a", arg))?;
+                controllers[if arg == "--controller" { 0 } else { 1 }].apply(&list)?;
+            }
+            "--fast-forward" => {
+                let value = args.next().ok_or("--fast-forward requires a speed, 0 for uncapped")?;
+                fast_forward = value.parse().map_err(|_| format!("Invalid fast forward speed {}", value))?;
+            }
+            "--fullscreen" | "--no-fullscreen" => window.fullscreen = arg == "--fullscreen",
+            "--grid" | "--no-grid" => window.grid = arg == "--grid",
+            _ if rom_path.is_none() && !arg.starts_with("--") => rom_path = Some(arg),
+            _ => return Err(format!("Unexpected argument {}", arg)),
+        }
+    }
+
+    if record_path.is_some() && replay_path.is_some() {
+        return Err("Can't record and replay at the same time".to_string());
     }
-    args.remove(1)
+
+    Ok(Options {
+        rom_path: rom_path.ok_or("Please provide valid filepath.")?,
+        record_path,
+        replay_path,
+        database_path,
+        sha1,
+        cache_dir,
+        platform,
+        tickrate,
+        quirks,
+        timing,
+        cdp1802,
+        palette,
+        foreground,
+        background,
+        persistence,
+        window,
+        hotkeys,
+        keymap,
+        keys,
+        controllers,
+        fast_forward,
+    })
 }
 
+// The first rom of archives and directories, urls are downloaded
 pub fn load_rom_from_path(path: &str) -> Vec<u8> {
-    std::fs::read(path).unwrap_or_else(|err| panic!("Error encountered while loading file from path {} : {}", path, err))
+    let roms = load_source(path, &Downloader::default()).unwrap_or_else(|err| panic!("{}", err));
+    roms.into_iter().next().unwrap_or_else(|| panic!("No roms found in {}", path)).data
 }
\ No newline at end of file
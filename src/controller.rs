@@ -0,0 +1,130 @@
+use std::str::FromStr;
+
+use crate::keys::MAX_KEY;
+
+pub const MAX_CONTROLLERS: usize = 2;
+
+// SDL game controller names, axes are bound with the direction as in leftx- or righty+
+const BUTTONS: [&str; 21] = [
+    "a", "b", "x", "y", "back", "guide", "start", "leftstick", "rightstick", "leftshoulder", "rightshoulder",
+    "dpup", "dpdown", "dpleft", "dpright", "misc1", "paddle1", "paddle2", "paddle3", "paddle4", "touchpad",
+];
+const AXES: [&str; 6] = ["leftx", "lefty", "rightx", "righty", "lefttrigger", "righttrigger"];
+
+// Most games steer with 5, 7, 8, 9 and act with 6
+const DEFAULT_BINDINGS: [(&str, u8); 9] = [
+    ("dpup", 0x5), ("dpleft", 0x7), ("dpdown", 0x8), ("dpright", 0x9),
+    ("lefty-", 0x5), ("leftx-", 0x7), ("lefty+", 0x8), ("leftx+", 0x9),
+    ("a", 0x6),
+];
+
+// Two player games like Pong put the second player on the right column of the keypad
+const PLAYER2_BINDINGS: [(&str, u8); 9] = [
+    ("dpup", 0xc), ("dpleft", 0xe), ("dpdown", 0xd), ("dpright", 0xf),
+    ("lefty-", 0xc), ("leftx-", 0xe), ("lefty+", 0xd), ("leftx+", 0xf),
+    ("a", 0xb),
+];
+
+// Controls for the actions named in the keys of the rom database, the player2 actions go to the second controller
+const ACTIONS: [(&str, &[&str]); 6] = [
+    ("up", &["dpup", "lefty-"]),
+    ("down", &["dpdown", "lefty+"]),
+    ("left", &["dpleft", "leftx-"]),
+    ("right", &["dpright", "leftx+"]),
+    ("a", &["a"]),
+    ("b", &["b"]),
+];
+
+pub fn is_control(name: &str) -> bool {
+    let axis = name.strip_suffix('+').or_else(|| name.strip_suffix('-'));
+    match axis {
+        Some(axis) => AXES.contains(&axis),
+        None => BUTTONS.contains(&name),
+    }
+}
+
+// Buttons and axis directions of a controller pressing CHIP-8 keys
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ControllerMap {
+    bindings: Vec<(String, u8)>,
+}
+
+impl ControllerMap {
+    pub fn new() -> Self {
+        ControllerMap { bindings: Vec::new() }
+    }
+
+    // A control presses a single CHIP-8 key, binding it again replaces the old key
+    pub fn bind(&mut self, control: &str, key: u8) -> Result<(), String> {
+        if key > MAX_KEY { return Err(format!("Invalid CHIP-8 key {:X} for {}", key, control)); }
+        self.insert(control, key);
+        Ok(())
+    }
+
+    fn insert(&mut self, control: &str, key: u8) {
+        self.bindings.retain(|(bound, _)| bound != control);
+        self.bindings.push((control.to_string(), key));
+    }
+
+    // Default layout of the first (0) or second (1) player
+    pub fn player(player: usize) -> Self {
+        let mut map = ControllerMap::new();
+        let bindings = if player == 0 { &DEFAULT_BINDINGS } else { &PLAYER2_BINDINGS };
+        bindings.iter().for_each(|(control, key)| map.insert(control, *key));
+        map
+    }
+
+    pub fn bindings(&self) -> &[(String, u8)] {
+        &self.bindings
+    }
+
+    pub fn key(&self, control: &str) -> Option<u8> {
+        self.bindings.iter().find(|(bound, _)| bound == control).map(|(_, key)| *key)
+    }
+
+    // Applies a list like 5=dpup,6=a with the CHIP-8 key in hex, 10 to 1F are the second CHIP-8X keypad
+    pub fn apply(&mut self, list: &str) -> Result<(), String> {
+        for binding in list.split(',').map(str::trim).filter(|binding| !binding.is_empty()) {
+            let (key, control) = binding.split_once('=').ok_or(format!("Invalid controller binding {}, use key=control", binding))?;
+            let key = match u8::from_str_radix(key.trim(), 16) {
+                Ok(key @ 0..=MAX_KEY) => key,
+                _ => return Err(format!("Invalid CHIP-8 key {} in {}", key, binding)),
+            };
+            let control = control.trim().to_lowercase();
+            if !is_control(&control) { return Err(format!("Unknown controller button or axis {}", control)); }
+            self.insert(&control, key);
+        }
+        Ok(())
+    }
+
+    // Binds the actions of a rom database entry for the first (0) or second (1) player
+    pub fn bind_actions(&mut self, actions: &[(String, u8)], player: usize) -> Result<(), String> {
+        for (action, key) in actions {
+            let name = match (player, action.strip_prefix("player2")) {
+                (0, None) => action.clone(),
+                (1, Some(name)) => name.to_lowercase(),
+                _ => continue,
+            };
+            let controls = ACTIONS.iter().find(|(action, _)| *action == name).map_or(&[][..], |(_, controls)| *controls);
+            for control in controls { self.bind(control, *key)? }
+        }
+        Ok(())
+    }
+}
+
+// The first controller steers with the d-pad and the left stick
+impl Default for ControllerMap {
+    fn default() -> Self {
+        ControllerMap::player(0)
+    }
+}
+
+impl FromStr for ControllerMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut map = ControllerMap::new();
+        map.apply(s)?;
+        Ok(map)
+    }
+}
@@ -0,0 +1,115 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ureq::Agent;
+
+use crate::rom_database::rom_hash;
+use crate::rom_loader::MAX_ROM_SIZE;
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+// File name at the end of the url, used to tell archives apart
+pub fn url_file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    match path.rsplit_once('/') {
+        Some((_, name)) if !name.is_empty() => name.to_string(),
+        _ => "download".to_string(),
+    }
+}
+
+// $XDG_CACHE_HOME/yac8, ~/.cache/yac8 or yac8 in the temporary directory
+pub fn default_cache_dir() -> PathBuf {
+    env::var_os("XDG_CACHE_HOME").map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(env::temp_dir)
+        .join("yac8")
+}
+
+// Downloads are stored in the cache under their sha1, which is also noted for the url in the urls directory. A url
+// downloaded before, or an expected sha1, is served from the cache without a request.
+#[derive(Clone, Debug)]
+pub struct Downloader {
+    cache_dir: Option<PathBuf>,
+    sha1: Option<String>,
+    max_size: usize,
+    timeout: Duration,
+}
+
+impl Downloader {
+    pub fn new() -> Self {
+        Downloader { cache_dir: Some(default_cache_dir()), sha1: None, max_size: MAX_ROM_SIZE, timeout: DEFAULT_TIMEOUT }
+    }
+
+    // None disables the cache
+    pub fn with_cache_dir(mut self, cache_dir: Option<PathBuf>) -> Self {
+        self.cache_dir = cache_dir;
+        self
+    }
+
+    pub fn with_sha1(mut self, sha1: Option<String>) -> Self {
+        self.sha1 = sha1.map(|sha1| sha1.to_lowercase());
+        self
+    }
+
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    // Limit for the whole request from connecting to the last byte
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn fetch(&self, url: &str) -> Result<Vec<u8>, String> {
+        if let Some(data) = self.cached(url) { return Ok(data); }
+
+        let agent: Agent = Agent::config_builder().timeout_global(Some(self.timeout)).build().into();
+        let error = |err: ureq::Error| format!("Error encountered while downloading {} : {}", url, err);
+        let mut response = agent.get(url).call().map_err(error)?;
+        let data = response.body_mut().with_config().limit(self.max_size as u64).read_to_vec().map_err(|err| match err {
+            ureq::Error::BodyExceedsLimit(_) => format!("{} is bigger than {} bytes", url, self.max_size),
+            err => error(err),
+        })?;
+
+        let hash = rom_hash(&data);
+        if let Some(sha1) = self.sha1.as_ref().filter(|sha1| **sha1 != hash) {
+            return Err(format!("{} has sha1 {} instead of {}", url, hash, sha1));
+        }
+        // a failing cache only costs the next download
+        if let Some(dir) = &self.cache_dir {
+            let _ = fs::create_dir_all(dir.join("urls"))
+                .and_then(|_| fs::write(dir.join(&hash), &data))
+                .and_then(|_| fs::write(url_index(dir, url), &hash));
+        }
+        Ok(data)
+    }
+
+    fn cached(&self, url: &str) -> Option<Vec<u8>> {
+        let dir = self.cache_dir.as_ref()?;
+        let sha1 = match &self.sha1 {
+            Some(sha1) => sha1.clone(),
+            None => fs::read_to_string(url_index(dir, url)).ok()?.trim().to_string(),
+        };
+        fs::read(dir.join(&sha1)).ok().filter(|data| rom_hash(data) == sha1)
+    }
+}
+
+// The file holding the sha1 of the last download from the url
+fn url_index(dir: &Path, url: &str) -> PathBuf {
+    dir.join("urls").join(rom_hash(url.as_bytes()))
+}
+
+impl Default for Downloader {
+    fn default() -> Self {
+        Downloader::new()
+    }
+}
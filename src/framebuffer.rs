@@ -0,0 +1,267 @@
+use core::fmt;
+
+// The screen is only as big as the platforms built in need, no_std builds without SUPER-CHIP keep the 64x32 one
+const LARGEST: Resolution = if cfg!(feature = "std") {
+    Resolution::Mega
+} else if cfg!(feature = "schip") {
+    Resolution::Hires
+} else {
+    Resolution::Lores
+};
+pub const MAX_WIDTH: usize = LARGEST.width();
+pub const MAX_HEIGHT: usize = LARGEST.height();
+// Every pixel keeps one bit per plane, XO-CHIP uses two of them
+pub const MAX_PLANES: u8 = if cfg!(feature = "xochip") { 2 } else { 1 };
+
+// On the heap with std, see Chip8
+#[cfg(feature = "std")]
+type Pixels = Box<[u8; MAX_WIDTH * MAX_HEIGHT]>;
+#[cfg(not(feature = "std"))]
+type Pixels = [u8; MAX_WIDTH * MAX_HEIGHT];
+
+#[cfg(feature = "std")]
+fn empty_pixels() -> Pixels {
+    Box::new([0; MAX_WIDTH * MAX_HEIGHT])
+}
+
+#[cfg(not(feature = "std"))]
+fn empty_pixels() -> Pixels {
+    [0; MAX_WIDTH * MAX_HEIGHT]
+}
+
+// Background and foreground of a single plane display
+pub const MONOCHROME: [[u8; 4]; 2] = [[0x00, 0x00, 0x00, 0xff], [0xff, 0xff, 0xff, 0xff]];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Resolution {
+    // 64x32 of the original interpreter
+    #[default]
+    Lores,
+    // 128x64 of SUPER-CHIP and XO-CHIP
+    Hires,
+    // 256x192 of MegaChip
+    Mega,
+}
+
+impl Resolution {
+    pub const fn width(&self) -> usize {
+        match self {
+            Resolution::Lores => 64,
+            Resolution::Hires => 128,
+            Resolution::Mega => 256,
+        }
+    }
+
+    pub const fn height(&self) -> usize {
+        match self {
+            Resolution::Lores => 32,
+            Resolution::Hires => 64,
+            Resolution::Mega => 192,
+        }
+    }
+}
+
+// Pixels of the current resolution row by row, each one holding the bits of all planes
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Framebuffer {
+    resolution: Resolution,
+    planes: u8,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_array"))]
+    pixels: Pixels,
+    // RGBA colours of the MegaChip screen, they replace the palette while in Mega resolution
+    #[cfg(feature = "std")]
+    colors: Vec<[u8; 4]>,
+}
+
+impl Framebuffer {
+    pub fn new(resolution: Resolution) -> Self {
+        let mut framebuffer = Framebuffer {
+            resolution,
+            planes: 1,
+            pixels: empty_pixels(),
+            #[cfg(feature = "std")]
+            colors: Vec::new(),
+        };
+        framebuffer.set_resolution(resolution);
+        framebuffer
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    pub fn width(&self) -> usize {
+        self.resolution.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.resolution.height()
+    }
+
+    // Switching the resolution clears the screen like the SCHIP mode switch instructions
+    pub fn set_resolution(&mut self, resolution: Resolution) {
+        assert!(resolution.width() <= MAX_WIDTH && resolution.height() <= MAX_HEIGHT, "{:?} is bigger than the screen of this build", resolution);
+        self.resolution = resolution;
+        #[cfg(feature = "std")]
+        {
+            let size = if resolution == Resolution::Mega { resolution.width() * resolution.height() } else { 0 };
+            self.colors.resize(size, MONOCHROME[0]);
+        }
+        self.clear();
+    }
+
+    pub fn planes(&self) -> u8 {
+        self.planes
+    }
+
+    // Pixels lose the bits of planes beyond the new count
+    pub fn set_planes(&mut self, planes: u8) {
+        assert!((1..=MAX_PLANES).contains(&planes), "{} planes are not supported", planes);
+        self.planes = planes;
+        let mask = (1 << planes) - 1;
+        self.pixels.iter_mut().for_each(|pixel| *pixel &= mask);
+    }
+
+    pub fn clear(&mut self) {
+        self.clear_planes(u8::MAX);
+        #[cfg(feature = "std")]
+        self.colors.fill(MONOCHROME[0]);
+    }
+
+    // Unlights the planes in mask and keeps the others
+    pub fn clear_planes(&mut self, mask: u8) {
+        self.pixels.iter_mut().for_each(|pixel| *pixel &= !mask);
+    }
+
+    // Value of the pixel with one bit per plane
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, value: u8) {
+        let index = self.index(x, y);
+        self.pixels[index] = value & ((1 << self.planes) - 1);
+    }
+
+    // Whether the pixel is lit on the first plane
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.pixel(x, y) & 1 != 0
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, lit: bool) {
+        let value = (self.pixel(x, y) & !1) | lit as u8;
+        self.set_pixel(x, y, value);
+    }
+
+    // XORs the planes in mask into the pixel, returns whether a lit pixel got turned off
+    pub fn toggle(&mut self, x: usize, y: usize, mask: u8) -> bool {
+        let index = self.index(x, y);
+        let collision = self.pixels[index] & mask != 0;
+        self.pixels[index] ^= mask & ((1 << self.planes) - 1);
+        collision
+    }
+
+    // Colour of the pixel on the MegaChip screen, None in the other resolutions
+    #[cfg(feature = "std")]
+    pub fn color(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        let index = self.index(x, y);
+        self.colors.get(index).copied()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn set_color(&mut self, x: usize, y: usize, color: [u8; 4]) {
+        let index = self.index(x, y);
+        self.colors[index] = color;
+    }
+
+    // Moves the screen right by dx and down by dy, pixels scrolled in are unlit
+    pub fn scroll(&mut self, dx: isize, dy: isize) {
+        self.scroll_planes(dx, dy, u8::MAX);
+    }
+
+    // Scrolls only the planes in mask, the others stay in place
+    pub fn scroll_planes(&mut self, dx: isize, dy: isize, mask: u8) {
+        let (width, height) = (self.width(), self.height());
+        scroll(&mut self.pixels[..width * height], width, dx, dy, |pixel, from| pixel & !mask | from.unwrap_or(0) & mask);
+        #[cfg(feature = "std")]
+        if !self.colors.is_empty() { scroll(&mut self.colors, width, dx, dy, |_, from| from.unwrap_or(MONOCHROME[0])) }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item=&[u8]> {
+        self.pixels[..self.width() * self.height()].chunks(self.width())
+    }
+
+    // Every pixel as (x, y, value) row by row
+    pub fn iter(&self) -> impl Iterator<Item=(usize, usize, u8)> + '_ {
+        self.rows().enumerate().flat_map(|(y, row)| row.iter().enumerate().map(move |(x, value)| (x, y, *value)))
+    }
+
+    // RGBA pixels row by row, the palette needs an entry for every value of the planes in use
+    pub fn as_rgba<'a>(&'a self, palette: &'a [[u8; 4]]) -> impl Iterator<Item=[u8; 4]> + 'a {
+        self.rows().flatten().enumerate().map(move |(index, value)| self.true_color(index).unwrap_or(palette[*value as usize]))
+    }
+
+    // FNV-1a over the pixels and colours, cheap enough to compare every cycle
+    pub fn hash(&self) -> u64 {
+        let colors = (0..self.width() * self.height()).filter_map(|index| self.true_color(index)).flatten();
+        self.rows().flatten().copied().chain(colors)
+            .fold(0xcbf2_9ce4_8422_2325, |hash, value| (hash ^ value as u64).wrapping_mul(0x0100_0000_01b3))
+    }
+
+    #[cfg(feature = "std")]
+    fn true_color(&self, index: usize) -> Option<[u8; 4]> {
+        self.colors.get(index).copied()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn true_color(&self, _index: usize) -> Option<[u8; 4]> {
+        None
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        assert!(x < self.width() && y < self.height(), "pixel ({}, {}) is outside of the screen", x, y);
+        y * self.width() + x
+    }
+}
+
+// Every cell becomes moved(cell, the cell dx and dy away or None past the edge)
+fn scroll<T: Copy>(cells: &mut [T], width: usize, dx: isize, dy: isize, moved: impl Fn(T, Option<T>) -> T) {
+    let height = cells.len() / width;
+    let source = |to: usize, by: isize, len: usize| to.checked_add_signed(-by).filter(|from| *from < len);
+    // walk away from the direction of movement so every cell is read before it is overwritten
+    let order = |i: usize, by: isize, len: usize| if by > 0 { len - 1 - i } else { i };
+    for y in (0..height).map(|i| order(i, dy, height)) {
+        for x in (0..width).map(|i| order(i, dx, width)) {
+            let from = match (source(x, dx, width), source(y, dy, height)) {
+                (Some(from_x), Some(from_y)) => Some(cells[from_y * width + from_x]),
+                _ => None,
+            };
+            cells[y * width + x] = moved(cells[y * width + x], from);
+        }
+    }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Framebuffer::new(Resolution::default())
+    }
+}
+
+// One line per row, '.' for unlit pixels, '#' for the first plane and the hex value otherwise
+impl fmt::Display for Framebuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for value in row {
+                match value {
+                    0 => write!(f, ".")?,
+                    1 => write!(f, "#")?,
+                    value => write!(f, "{:x}", value)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
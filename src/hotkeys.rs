@@ -0,0 +1,127 @@
+use std::str::FromStr;
+
+use crate::keymap::Keymap;
+
+// Requests to the frontend, queued until the next frame
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    NextTheme,
+    ToggleFullscreen,
+    ToggleGrid,
+    ScaleUp,
+    ScaleDown,
+    ToggleRegisters,
+    ToggleStats,
+    Pause,
+    FrameAdvance,
+    // Held down rather than pressed
+    FastForward,
+    SlowMotion,
+    Reset,
+    HardReset,
+    SaveState,
+    LoadState,
+    Quit,
+    // Sent by the window, there is no key for it
+    Redraw,
+}
+
+const COMMAND_NAMES: [(&str, Command); 16] = [
+    ("theme", Command::NextTheme),
+    ("fullscreen", Command::ToggleFullscreen),
+    ("grid", Command::ToggleGrid),
+    ("scale_up", Command::ScaleUp),
+    ("scale_down", Command::ScaleDown),
+    ("registers", Command::ToggleRegisters),
+    ("stats", Command::ToggleStats),
+    ("pause", Command::Pause),
+    ("frame_advance", Command::FrameAdvance),
+    ("fast_forward", Command::FastForward),
+    ("slow_motion", Command::SlowMotion),
+    ("reset", Command::Reset),
+    ("hard_reset", Command::HardReset),
+    ("save_state", Command::SaveState),
+    ("load_state", Command::LoadState),
+    ("quit", Command::Quit),
+];
+
+// Keys are SDL scancode names, none of them is used by a keymap preset
+const DEFAULT_BINDINGS: [(&str, Command); 17] = [
+    ("F1", Command::SaveState),
+    ("F2", Command::NextTheme),
+    ("F3", Command::ToggleGrid),
+    ("F4", Command::ToggleRegisters),
+    ("F5", Command::ToggleStats),
+    ("F6", Command::Pause),
+    ("F7", Command::FrameAdvance),
+    ("Tab", Command::FastForward),
+    ("F8", Command::SlowMotion),
+    ("F9", Command::Reset),
+    ("F10", Command::HardReset),
+    ("F11", Command::ToggleFullscreen),
+    ("F12", Command::LoadState),
+    ("=", Command::ScaleUp),
+    ("-", Command::ScaleDown),
+    ("Escape", Command::Quit),
+    ("Pause", Command::Pause),
+];
+
+impl Command {
+    pub fn name(&self) -> &'static str {
+        COMMAND_NAMES.iter().find(|(_, command)| command == self).map_or("redraw", |(name, _)| name)
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        COMMAND_NAMES.iter().find(|(name, _)| *name == s).map(|(_, command)| *command).ok_or(format!("Unknown command {}", s))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Hotkeys {
+    bindings: Vec<(String, Command)>,
+}
+
+impl Hotkeys {
+    // A key triggers a single command, binding it again replaces the old command
+    pub fn bind(&mut self, key: &str, command: Command) {
+        self.bindings.retain(|(bound, _)| !bound.eq_ignore_ascii_case(key));
+        self.bindings.push((key.to_string(), command));
+    }
+
+    pub fn bindings(&self) -> &[(String, Command)] {
+        &self.bindings
+    }
+
+    pub fn command(&self, key: &str) -> Option<Command> {
+        self.bindings.iter().find(|(bound, _)| bound.eq_ignore_ascii_case(key)).map(|(_, command)| *command)
+    }
+
+    // Hotkeys on keys of the keymap along with the CHIP-8 key that wins, names are compared by what resolve makes
+    // of them so different names of the same key match
+    pub fn conflicts<K: PartialEq>(&self, keymap: &Keymap, resolve: impl Fn(&str) -> Option<K>) -> Vec<(String, Command, u8)> {
+        let keys: Vec<(K, u8)> = keymap.bindings().iter().filter_map(|(host, key)| resolve(host).map(|host| (host, *key))).collect();
+        self.bindings.iter().filter_map(|(name, command)| {
+            let resolved = resolve(name)?;
+            keys.iter().find(|(host, _)| *host == resolved).map(|(_, key)| (name.clone(), *command, *key))
+        }).collect()
+    }
+
+    // Applies a list like pause=P,reset=Backspace
+    pub fn apply(&mut self, list: &str) -> Result<(), String> {
+        for binding in list.split(',').map(str::trim).filter(|binding| !binding.is_empty()) {
+            let (command, key) = binding.split_once('=').ok_or(format!("Invalid hotkey {}, use command=key", binding))?;
+            self.bind(key.trim(), command.trim().parse()?);
+        }
+        Ok(())
+    }
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        Hotkeys { bindings: DEFAULT_BINDINGS.iter().map(|(key, command)| (key.to_string(), *command)).collect() }
+    }
+}
@@ -1,49 +1,176 @@
-use sdl2::event::Event;
-use sdl2::keyboard::Scancode;
+use sdl2::controller::{Axis, Button, GameController};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::GameControllerSubsystem;
+use sdl2::keyboard::{Keycode, Scancode};
+
+use crate::controller::{ControllerMap, MAX_CONTROLLERS};
+use crate::hotkeys::{Command, Hotkeys};
+use crate::keymap::Keymap;
+use crate::keys::Keys;
+
+// Keys steering a menu while it is open
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MenuKey {
+    Up,
+    Down,
+    Select,
+    Cancel,
+}
+
+// Half way to the end of the axis
+const AXIS_THRESHOLD: i16 = 0x4000;
+
+#[derive(Clone, Copy)]
+enum Control {
+    Button(Button),
+    // pressed when pushed towards the positive or the negative end
+    Axis(Axis, bool),
+}
 
 pub struct Input {
     event_pump: sdl2::EventPump,
+    hotkeys: Vec<(Scancode, Command)>,
+    keymap: Vec<(Scancode, u8)>,
+    controller_subsystem: GameControllerSubsystem,
+    controllers: [Option<GameController>; MAX_CONTROLLERS],
+    controller_maps: Vec<Vec<(Control, u8)>>,
+    pub quit: bool,
+    pub commands: Vec<Command>,
+    pub fast_forward: bool,
+    pub menu_open: bool,
+    pub menu_keys: Vec<MenuKey>,
+    // Hotkeys lost to the keymap and controllers connected and disconnected
+    pub messages: Vec<String>,
 }
 
 impl Input {
-    pub fn new(sdl_context: &sdl2::Sdl) -> Self {
-        Input { event_pump: sdl_context.event_pump().unwrap() }
+    pub fn new(sdl_context: &sdl2::Sdl, hotkeys: &Hotkeys, keymap: &Keymap, controllers: &[ControllerMap]) -> Result<Self, String> {
+        let mut keys = Vec::new();
+        for (host, key) in keymap.bindings() {
+            let scancode = scancode(host).ok_or(format!("Unknown key {} for CHIP-8 key {:X}", host, key))?;
+            keys.push((scancode, *key));
+        }
+
+        // keys pressing CHIP-8 keys don't trigger hotkeys, the player is told which ones are lost
+        let conflicts = hotkeys.conflicts(keymap, scancode);
+        let messages = conflicts.iter()
+            .map(|(key, command, chip8_key)| format!("{} presses CHIP-8 key {:X}, hotkey {} is off", key, chip8_key, command.name()))
+            .collect();
+        let mut bindings = Vec::new();
+        for (key, command) in hotkeys.bindings() {
+            let scancode = scancode(key).ok_or(format!("Unknown key {} for {}", key, command.name()))?;
+            if !keys.iter().any(|(bound, _)| *bound == scancode) { bindings.push((scancode, *command)) }
+        }
+
+        let mut controller_maps = Vec::new();
+        for map in controllers {
+            let controls = map.bindings().iter()
+                .map(|(name, key)| to_control(name).map(|control| (control, *key)).ok_or(format!("Unknown controller button or axis {}", name)))
+                .collect::<Result<_, _>>()?;
+            controller_maps.push(controls);
+        }
+
+        // already connected controllers are announced as added by the first events
+        Ok(Input {
+            event_pump: sdl_context.event_pump()?,
+            hotkeys: bindings,
+            keymap: keys,
+            controller_subsystem: sdl_context.game_controller()?,
+            controllers: Default::default(),
+            controller_maps,
+            quit: false,
+            commands: Vec::new(),
+            fast_forward: false,
+            menu_open: false,
+            menu_keys: Vec::new(),
+            messages,
+        })
     }
 
-    pub fn poll_keys(&mut self) -> Option<u8> {
+    pub fn poll_keys(&mut self) -> Keys {
         for ev in self.event_pump.poll_iter() {
-            if let Event::Quit { .. } = ev {
-                panic!("Quit event")
+            match ev {
+                Event::Quit { .. } => self.quit = true,
+                Event::KeyDown { scancode: Some(scancode), repeat: false, .. } if self.menu_open => {
+                    if let Some(key) = to_menu_key(scancode) { self.menu_keys.push(key) }
+                }
+                Event::KeyDown { scancode: Some(scancode), repeat: false, .. } => match command(&self.hotkeys, scancode) {
+                    Some(Command::FastForward) | None => {}
+                    Some(command) => self.commands.push(command),
+                },
+                Event::ControllerDeviceAdded { which, .. } => {
+                    connect(&self.controller_subsystem, &mut self.controllers, which, &mut self.messages)
+                }
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    let slot = self.controllers.iter().position(|controller| controller.as_ref().is_some_and(|c| c.instance_id() == which));
+                    if let Some(slot) = slot {
+                        self.controllers[slot] = None;
+                        self.messages.push(format!("Controller {} disconnected", slot + 1));
+                    }
+                }
+                Event::Window { win_event: WindowEvent::SizeChanged(..) | WindowEvent::Exposed, .. } => {
+                    self.commands.push(Command::Redraw)
+                }
+                _ => {}
             }
         }
 
-        for (scancode, res) in self.event_pump.keyboard_state().scancodes() {
-            //TODO handle multiple key presses at once
-            if let (Some(key), true) = (self.to_chip8_key(scancode), res) { return Some(key); };
-        }
+        let keyboard = self.event_pump.keyboard_state();
+        self.fast_forward = self.hotkeys.iter()
+            .any(|(scancode, command)| *command == Command::FastForward && keyboard.is_scancode_pressed(*scancode));
 
-        None
+        // everything held on the keyboard and both controllers, so two players can press keys together
+        let keys = self.keymap.iter().filter(|(scancode, _)| keyboard.is_scancode_pressed(*scancode)).map(|(_, key)| *key);
+        let buttons = self.controllers.iter().zip(&self.controller_maps)
+            .filter_map(|(controller, map)| controller.as_ref().map(|controller| (controller, map)))
+            .flat_map(|(controller, map)| map.iter().filter(|(control, _)| is_pressed(controller, *control)).map(|(_, key)| *key));
+        keys.chain(buttons).collect()
     }
+}
 
-    fn to_chip8_key(&self, scancode: Scancode) -> Option<u8> {
-        match scancode {
-            Scancode::Num1 => Some(0x1),
-            Scancode::Num2 => Some(0x2),
-            Scancode::Num3 => Some(0x3),
-            Scancode::Num4 => Some(0xc),
-            Scancode::Q => Some(0x4),
-            Scancode::W => Some(0x5),
-            Scancode::E => Some(0x6),
-            Scancode::R => Some(0xd),
-            Scancode::A => Some(0x7),
-            Scancode::S => Some(0x8),
-            Scancode::D => Some(0x9),
-            Scancode::F => Some(0xe),
-            Scancode::Z => Some(0xa),
-            Scancode::X => Some(0x0),
-            Scancode::C => Some(0xb),
-            Scancode::V => Some(0xf),
-            _ => { None }
+fn command(hotkeys: &[(Scancode, Command)], scancode: Scancode) -> Option<Command> {
+    hotkeys.iter().find(|(bound, _)| *bound == scancode).map(|(_, command)| *command)
+}
+
+// Takes the first free slot, controllers beyond MAX_CONTROLLERS are ignored
+fn connect(subsystem: &GameControllerSubsystem, controllers: &mut [Option<GameController>], index: u32, messages: &mut Vec<String>) {
+    let Some(slot) = controllers.iter().position(Option::is_none) else { return };
+    match subsystem.open(index) {
+        Ok(controller) => {
+            messages.push(format!("Controller {} connected: {}", slot + 1, controller.name()));
+            controllers[slot] = Some(controller);
         }
+        Err(err) => messages.push(format!("Controller not opened: {}", err)),
     }
 }
+
+fn to_control(name: &str) -> Option<Control> {
+    match (name.strip_suffix('+'), name.strip_suffix('-')) {
+        (Some(axis), _) => Axis::from_string(axis).map(|axis| Control::Axis(axis, true)),
+        (_, Some(axis)) => Axis::from_string(axis).map(|axis| Control::Axis(axis, false)),
+        _ => Button::from_string(name).map(Control::Button),
+    }
+}
+
+fn is_pressed(controller: &GameController, control: Control) -> bool {
+    match control {
+        Control::Button(button) => controller.button(button),
+        Control::Axis(axis, true) => controller.axis(axis) > AXIS_THRESHOLD,
+        Control::Axis(axis, false) => controller.axis(axis) < -AXIS_THRESHOLD,
+    }
+}
+
+fn to_menu_key(scancode: Scancode) -> Option<MenuKey> {
+    match scancode {
+        Scancode::Up => Some(MenuKey::Up),
+        Scancode::Down => Some(MenuKey::Down),
+        Scancode::Return | Scancode::KpEnter => Some(MenuKey::Select),
+        Scancode::Escape => Some(MenuKey::Cancel),
+        _ => None,
+    }
+}
+
+// Key names are looked up in the current keyboard layout first, then as the physical key
+fn scancode(name: &str) -> Option<Scancode> {
+    Keycode::from_name(name).and_then(Scancode::from_keycode).or_else(|| Scancode::from_name(name))
+}
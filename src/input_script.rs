@@ -0,0 +1,84 @@
+use std::str::FromStr;
+
+use crate::keys::Keys;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Timing {
+    Frame(u64),
+    Cycle(u64),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct ScriptEvent {
+    at: Timing,
+    key: u8,
+    pressed: bool,
+}
+
+// Keys are pressed and released at a given frame or cycle, e.g. "frame 10: press 5; frame 12: release 5".
+// Every key held at the time is reported.
+#[derive(Clone, Default, Debug)]
+pub struct InputScript {
+    events: Vec<ScriptEvent>,
+    pressed: Keys,
+    // frame events fire on the first cycle of their frame only
+    frame: Option<u64>,
+}
+
+impl InputScript {
+    pub fn new() -> Self {
+        InputScript::default()
+    }
+
+    pub fn press(mut self, at: Timing, key: u8) -> Self {
+        self.events.push(ScriptEvent { at, key, pressed: true });
+        self
+    }
+
+    pub fn release(mut self, at: Timing, key: u8) -> Self {
+        self.events.push(ScriptEvent { at, key, pressed: false });
+        self
+    }
+
+    pub fn keys_at(&mut self, frame: u64, cycle: u64) -> Keys {
+        let new_frame = self.frame.replace(frame) != Some(frame);
+        for event in self.events.iter() {
+            let due = match event.at {
+                Timing::Frame(f) => new_frame && f == frame,
+                Timing::Cycle(c) => c == cycle,
+            };
+            if due {
+                if event.pressed { self.pressed.press(event.key) } else { self.pressed.release(event.key) }
+            }
+        }
+
+        self.pressed
+    }
+}
+
+impl FromStr for InputScript {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut script = InputScript::new();
+        for event in s.split(';').map(str::trim).filter(|event| !event.is_empty()) {
+            let invalid = || format!("Invalid input script event '{}'", event);
+            let (timing, action) = event.split_once(':').ok_or_else(invalid)?;
+            let (unit, n) = timing.trim().split_once(' ').ok_or_else(invalid)?;
+            let n = n.trim().parse().map_err(|_| invalid())?;
+            let at = match unit {
+                "frame" => Timing::Frame(n),
+                "cycle" => Timing::Cycle(n),
+                _ => return Err(invalid()),
+            };
+            let (action, key) = action.trim().split_once(' ').ok_or_else(invalid)?;
+            let key = u8::from_str_radix(key.trim(), 16).ok().filter(|k| *k <= 0xf).ok_or_else(invalid)?;
+            script = match action {
+                "press" => script.press(at, key),
+                "release" => script.release(at, key),
+                _ => return Err(invalid()),
+            };
+        }
+        Ok(script)
+    }
+}
@@ -0,0 +1,139 @@
+use std::str::FromStr;
+
+use crate::keys::MAX_KEY;
+
+// Keys are SDL key names, looked up by their label in the current keyboard layout
+const QWERTY: [(&str, u8); 16] = [
+    ("1", 0x1), ("2", 0x2), ("3", 0x3), ("4", 0xc),
+    ("Q", 0x4), ("W", 0x5), ("E", 0x6), ("R", 0xd),
+    ("A", 0x7), ("S", 0x8), ("D", 0x9), ("F", 0xe),
+    ("Z", 0xa), ("X", 0x0), ("C", 0xb), ("V", 0xf),
+];
+
+const AZERTY: [(&str, u8); 16] = [
+    ("1", 0x1), ("2", 0x2), ("3", 0x3), ("4", 0xc),
+    ("A", 0x4), ("Z", 0x5), ("E", 0x6), ("R", 0xd),
+    ("Q", 0x7), ("S", 0x8), ("D", 0x9), ("F", 0xe),
+    ("W", 0xa), ("X", 0x0), ("C", 0xb), ("V", 0xf),
+];
+
+const DVORAK: [(&str, u8); 16] = [
+    ("1", 0x1), ("2", 0x2), ("3", 0x3), ("4", 0xc),
+    ("'", 0x4), (",", 0x5), (".", 0x6), ("P", 0xd),
+    ("A", 0x7), ("O", 0x8), ("E", 0x9), ("U", 0xe),
+    (";", 0xa), ("Q", 0x0), ("J", 0xb), ("K", 0xf),
+];
+
+// The digits on their own keys, the remaining keys hold A to F
+const NUMPAD: [(&str, u8); 16] = [
+    ("Keypad 0", 0x0), ("Keypad 1", 0x1), ("Keypad 2", 0x2), ("Keypad 3", 0x3),
+    ("Keypad 4", 0x4), ("Keypad 5", 0x5), ("Keypad 6", 0x6), ("Keypad 7", 0x7),
+    ("Keypad 8", 0x8), ("Keypad 9", 0x9), ("Keypad .", 0xa), ("Keypad Enter", 0xb),
+    ("Keypad +", 0xc), ("Keypad -", 0xd), ("Keypad *", 0xe), ("Keypad /", 0xf),
+];
+
+// Most games steer with 5, 7, 8, 9 and act with 6, added on top of QWERTY
+const ARROWS: [(&str, u8); 5] = [("Up", 0x5), ("Left", 0x7), ("Down", 0x8), ("Right", 0x9), ("Space", 0x6)];
+
+pub const PRESETS: [&str; 5] = ["qwerty", "azerty", "dvorak", "numpad", "arrows"];
+
+// Host keys for the actions named in the keys of the rom database
+const ACTIONS: [(&str, &str); 12] = [
+    ("up", "Up"),
+    ("down", "Down"),
+    ("left", "Left"),
+    ("right", "Right"),
+    ("a", "Space"),
+    ("b", "Left Shift"),
+    ("player2Up", "I"),
+    ("player2Down", "K"),
+    ("player2Left", "J"),
+    ("player2Right", "L"),
+    ("player2A", "U"),
+    ("player2B", "O"),
+];
+
+// Host keys pressing CHIP-8 keys, several host keys can press the same CHIP-8 key
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Keymap {
+    bindings: Vec<(String, u8)>,
+}
+
+impl Keymap {
+    pub fn new() -> Self {
+        Keymap { bindings: Vec::new() }
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        let bindings: &[(&str, u8)] = match name {
+            "qwerty" | "arrows" => &QWERTY,
+            "azerty" => &AZERTY,
+            "dvorak" => &DVORAK,
+            "numpad" => &NUMPAD,
+            _ => return None,
+        };
+        let mut keymap = Keymap::new();
+        bindings.iter().for_each(|(host, key)| keymap.insert(host, *key));
+        if name == "arrows" { ARROWS.iter().for_each(|(host, key)| keymap.insert(host, *key)) }
+        Some(keymap)
+    }
+
+    // A host key presses a single CHIP-8 key, binding it again replaces the old key
+    pub fn bind(&mut self, host: &str, key: u8) -> Result<(), String> {
+        if key > MAX_KEY { return Err(format!("Invalid CHIP-8 key {:X} for {}", key, host)); }
+        self.insert(host, key);
+        Ok(())
+    }
+
+    fn insert(&mut self, host: &str, key: u8) {
+        self.bindings.retain(|(bound, _)| !bound.eq_ignore_ascii_case(host));
+        self.bindings.push((host.to_string(), key));
+    }
+
+    pub fn bindings(&self) -> &[(String, u8)] {
+        &self.bindings
+    }
+
+    pub fn key(&self, host: &str) -> Option<u8> {
+        self.bindings.iter().find(|(bound, _)| bound.eq_ignore_ascii_case(host)).map(|(_, key)| *key)
+    }
+
+    // Applies a list like 5=Up,5=W with the CHIP-8 key in hex, 10 to 1F are the second CHIP-8X keypad
+    pub fn apply(&mut self, list: &str) -> Result<(), String> {
+        for binding in list.split(',').map(str::trim).filter(|binding| !binding.is_empty()) {
+            let (key, host) = binding.split_once('=').ok_or(format!("Invalid key binding {}, use key=host key", binding))?;
+            let key = match u8::from_str_radix(key.trim(), 16) {
+                Ok(key @ 0..=MAX_KEY) => key,
+                _ => return Err(format!("Invalid CHIP-8 key {} in {}", key, binding)),
+            };
+            self.insert(host.trim(), key);
+        }
+        Ok(())
+    }
+
+    // Binds the actions of a rom database entry, unknown actions are ignored
+    pub fn bind_actions(&mut self, actions: &[(String, u8)]) -> Result<(), String> {
+        for (action, key) in actions {
+            if let Some((_, host)) = ACTIONS.iter().find(|(name, _)| name == action) { self.bind(host, *key)? }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::preset("qwerty").unwrap()
+    }
+}
+
+// A preset name or a list of bindings
+impl FromStr for Keymap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(keymap) = Keymap::preset(s) { return Ok(keymap); }
+        let mut keymap = Keymap::new();
+        keymap.apply(s)?;
+        Ok(keymap)
+    }
+}
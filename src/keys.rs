@@ -0,0 +1,46 @@
+use core::iter::FromIterator;
+
+// 0 to F are the main keypad, 10 to 1F the second keypad of the CHIP-8X
+pub const MAX_KEY: u8 = 0x1f;
+
+// The keys held during a step, every player and keypad at once
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Hash)]
+pub struct Keys(u32);
+
+impl Keys {
+    pub const NONE: Keys = Keys(0);
+
+    pub fn new() -> Self {
+        Keys::NONE
+    }
+
+    pub fn contains(&self, key: u8) -> bool {
+        key <= MAX_KEY && self.0 & (1 << key) != 0
+    }
+
+    // Keys above MAX_KEY are ignored
+    pub fn press(&mut self, key: u8) {
+        if key <= MAX_KEY { self.0 |= 1 << key }
+    }
+
+    pub fn release(&mut self, key: u8) {
+        if key <= MAX_KEY { self.0 &= !(1 << key) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    // Held keys from the lowest up
+    pub fn iter(&self) -> impl Iterator<Item=u8> + '_ {
+        (0..=MAX_KEY).filter(|key| self.contains(*key))
+    }
+}
+
+impl FromIterator<u8> for Keys {
+    fn from_iter<I: IntoIterator<Item=u8>>(iter: I) -> Self {
+        let mut keys = Keys::new();
+        iter.into_iter().for_each(|key| keys.press(key));
+        keys
+    }
+}
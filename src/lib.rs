@@ -1,8 +1,49 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "sdl")]
+mod audio;
 pub mod chip8;
+pub mod cdp1802;
+pub mod framebuffer;
+pub mod keys;
+#[cfg(feature = "std")]
+pub mod hotkeys;
+#[cfg(feature = "std")]
+pub mod keymap;
+#[cfg(feature = "std")]
 pub mod cli;
+#[cfg(feature = "std")]
+pub mod controller;
+#[cfg(feature = "std")]
+pub mod download;
 pub mod chip8_runner;
+#[cfg(feature = "std")]
+pub mod movie;
+#[cfg(feature = "std")]
+pub mod osd;
+pub mod palette;
+pub mod persistence;
 pub mod quirks;
+pub mod render;
+#[cfg(feature = "std")]
+pub mod quirk_detection;
+pub mod rng;
+#[cfg(feature = "std")]
+pub mod rom_database;
+#[cfg(feature = "std")]
+pub mod rom_loader;
+#[cfg(feature = "std")]
+pub mod rom_picker;
+#[cfg(feature = "std")]
+pub mod settings;
+pub mod timing;
 mod bit_ops;
+#[cfg(feature = "sdl")]
 mod sdl_driver;
+#[cfg(feature = "sdl")]
 mod input;
-mod registers;
\ No newline at end of file
+#[cfg(feature = "std")]
+pub mod input_script;
+mod registers;
+#[cfg(feature = "serde")]
+mod serde_array;
\ No newline at end of file
@@ -1,13 +1,83 @@
 extern crate core;
 
-use yac8::chip8::{Chip8};
-use yac8::chip8_runner::{run_with_sdl};
-use yac8::cli::load_from_cli;
+use std::env;
+use std::io;
+use std::path::Path;
+
+use yac8::chip8::{Chip8, Config};
+use yac8::chip8_runner::run_with_sdl;
+use yac8::cli::parse_args;
+use yac8::movie::{Movie, Session};
+use yac8::quirk_detection::detect_with_execution;
+use yac8::rom_database::{rom_hash, RomDatabase};
+use yac8::rom_loader::load_source;
+use yac8::rom_picker::RomPicker;
+use yac8::settings::Settings;
+
+const DETECTION_FRAMES: u64 = 120;
 
 pub fn main() -> Result<(), String> {
-    let rom = load_from_cli();
-    let mut chip8 = Chip8::default(rom);
-    run_with_sdl(&mut chip8, 20);
+    // the config file comes first so the command line overrides it
+    let args: Vec<String> = env::args().skip(1).collect();
+    let settings = Settings::find(&args)?;
+    let options = parse_args(settings.to_args().into_iter().chain(args.iter().cloned()))?;
+    let database = match &options.database_path {
+        Some(path) => RomDatabase::load(Path::new(path))?,
+        None => RomDatabase::builtin(),
+    };
+
+    let mut roms = load_source(&options.rom_path, &options.downloader())?;
+    let rom = match roms.len() {
+        0 => return Err(format!("No roms found in {}", options.rom_path)),
+        1 => roms.remove(0).data,
+        _ => match RomPicker::new(&roms, &database).pick(io::stdin().lock(), io::stdout())? {
+            Some(index) => roms.swap_remove(index).data,
+            None => return Ok(()),
+        },
+    };
+    // parsed again with the settings of the rom in between
+    let options = parse_args(settings.args_for(&rom_hash(&rom)).into_iter().chain(args))?;
+    let info = database.lookup(&rom);
+    let config = match info {
+        Some(info) => {
+            println!("Loaded {} ({:?})", info.title, info.platform);
+            info.config()
+        }
+        None if options.platform.is_none() => {
+            let report = detect_with_execution(&rom, DETECTION_FRAMES);
+            print!("{}", report);
+            report.config()
+        }
+        None => Config::ch8(),
+    };
+    let overridden = options.apply_overrides(config.clone());
+    for (name, from, to) in config.quirks().diff(overridden.quirks()) {
+        println!("Quirk {} overridden: {} -> {}", name, from, to);
+    }
+    let config = overridden;
+
+    let (config, mut session) = match (&options.record_path, &options.replay_path) {
+        (_, Some(path)) => {
+            let movie = Movie::load(Path::new(path))?;
+            movie.check_rom(&rom)?;
+            // the recording decides how the rom runs, the command line how it looks
+            let replayed = movie.config().with_palette(*config.palette()).with_persistence(config.persistence());
+            (replayed, Session::Replay(movie, 0))
+        }
+        (Some(_), _) => {
+            let movie = Movie::new(&rom, &config);
+            (config, Session::Record(movie))
+        }
+        _ => (config, Session::Live),
+    };
+
+    let mut chip8 = Chip8::new(&rom, config).map_err(|err| err.to_string())?;
+    let rom_keys = info.map_or(&[][..], |info| &info.keys);
+    run_with_sdl(&mut chip8, &rom, &options, &options.keymap(rom_keys)?, &options.controller_maps(rom_keys)?, &mut session)?;
+
+    if let (Some(path), Session::Record(movie)) = (&options.record_path, &session) {
+        movie.save(Path::new(path))?;
+    }
 
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,182 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::chip8::Config;
+use crate::keys::{Keys, MAX_KEY};
+use crate::quirks::{Platform, Quirks};
+use crate::rng::RngMode;
+use crate::rom_database::rom_hash;
+use crate::timing::TimingMode;
+
+// Version 2 stores sets of keys per frame and the half_pixel_scroll and dxy0 quirk bits
+const HEADER: &str = "yac8-movie 2";
+// A day at 60 frames per second, longer movies are rejected before their frames are allocated
+pub const MAX_FRAMES: usize = 60 * 60 * 60 * 24;
+
+pub struct Movie {
+    rom_hash: String,
+    quirks: u32,
+    seed: u32,
+    rng_mode: RngMode,
+    cycles_per_frame: u32,
+    timing: TimingMode,
+    platform: Platform,
+    cdp1802: bool,
+    frames: Vec<Keys>,
+}
+
+impl Movie {
+    pub fn new(rom: &[u8], config: &Config) -> Self {
+        Movie {
+            rom_hash: rom_hash(rom),
+            quirks: config.quirks().to_bits(),
+            seed: config.seed(),
+            rng_mode: config.rng_mode(),
+            cycles_per_frame: config.cycles_per_frame(),
+            timing: config.timing(),
+            platform: config.platform(),
+            cdp1802: config.cdp1802(),
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn record_frame(&mut self, keys: Keys) {
+        self.frames.push(keys);
+    }
+
+    pub fn frames(&self) -> &[Keys] {
+        &self.frames
+    }
+
+    // The emulation settings of the recording, the display options are left to the player
+    pub fn config(&self) -> Config {
+        Config::ch8()
+            .with_platform(self.platform)
+            .with_quirks(Quirks::from_bits(self.quirks))
+            .with_seed(self.seed)
+            .with_rng_mode(self.rng_mode)
+            .with_cycles_per_frame(self.cycles_per_frame)
+            .with_timing(self.timing)
+            .with_cdp1802(self.cdp1802)
+    }
+
+    pub fn check_rom(&self, rom: &[u8]) -> Result<(), String> {
+        let hash = rom_hash(rom);
+        if hash == self.rom_hash { Ok(()) } else { Err(format!("Movie was recorded with rom {} but got {}", self.rom_hash, hash)) }
+    }
+
+    pub fn load(path: &Path) -> Result<Movie, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("Error encountered while loading movie from path {} : {}", path.display(), err))?;
+        Movie::parse(&text)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.serialize()).map_err(|err| format!("Error encountered while saving movie to path {} : {}", path.display(), err))
+    }
+
+    pub fn parse(text: &str) -> Result<Movie, String> {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.next() != Some(HEADER) { return Err("Not a yac8 movie".to_string()); }
+
+        let mut field = |name: &str| -> Result<String, String> {
+            match lines.next().and_then(|line| line.split_once(' ')) {
+                Some((key, value)) if key == name => Ok(value.to_string()),
+                _ => Err(format!("Movie is missing field {}", name)),
+            }
+        };
+
+        let rom_hash = field("rom")?;
+        let quirks = u32::from_str_radix(&field("quirks")?, 16).map_err(|err| err.to_string())?;
+        let seed = field("seed")?.parse().map_err(|err: std::num::ParseIntError| err.to_string())?;
+        let rng_mode = match field("rng")?.as_str() {
+            "xorshift" => RngMode::XorShift,
+            "vip" => RngMode::CosmacVip,
+            other => return Err(format!("Unknown rng {}", other)),
+        };
+        let cycles_per_frame = field("cycles")?.parse().ok().filter(|cycles| *cycles > 0).ok_or("Invalid cycles per frame")?;
+        let timing = match field("timing")?.as_str() {
+            "vip" => TimingMode::CosmacVip,
+            "instructions" => TimingMode::Instructions,
+            other => return Err(format!("Unknown timing {}", other)),
+        };
+        let platform: Platform = field("platform")?.parse().map_err(|_| "Unknown platform in movie")?;
+        let cdp1802 = match field("cdp1802")?.as_str() {
+            "on" => true,
+            "off" => false,
+            other => return Err(format!("Unknown cdp1802 {}", other)),
+        };
+        if lines.next() != Some("frames") { return Err("Movie is missing frames".to_string()); }
+
+        let mut frames = Vec::new();
+        for line in lines {
+            let (count, held) = line.split_once(' ').ok_or(format!("Invalid frame {}", line))?;
+            let count: usize = count.parse().map_err(|_| format!("Invalid frame {}", line))?;
+            if count > MAX_FRAMES - frames.len() { return Err(format!("Movie is longer than {} frames", MAX_FRAMES)); }
+            let mut keys = Keys::new();
+            for k in held.split(',').filter(|k| *k != "-") {
+                keys.press(u8::from_str_radix(k, 16).ok().filter(|k| *k <= MAX_KEY).ok_or(format!("Invalid key {}", k))?);
+            }
+            frames.extend(std::iter::repeat_n(keys, count));
+        }
+
+        Ok(Movie { rom_hash, quirks, seed, rng_mode, cycles_per_frame, timing, platform, cdp1802, frames })
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut text = String::new();
+        writeln!(text, "{}", HEADER).unwrap();
+        writeln!(text, "rom {}", self.rom_hash).unwrap();
+        writeln!(text, "quirks {:x}", self.quirks).unwrap();
+        writeln!(text, "seed {}", self.seed).unwrap();
+        writeln!(text, "rng {}", match self.rng_mode { RngMode::XorShift => "xorshift", RngMode::CosmacVip => "vip" }).unwrap();
+        writeln!(text, "cycles {}", self.cycles_per_frame).unwrap();
+        writeln!(text, "timing {}", match self.timing { TimingMode::Instructions => "instructions", TimingMode::CosmacVip => "vip" }).unwrap();
+        writeln!(text, "platform {}", self.platform.name()).unwrap();
+        writeln!(text, "cdp1802 {}", if self.cdp1802 { "on" } else { "off" }).unwrap();
+        writeln!(text, "frames").unwrap();
+
+        let mut frames = self.frames.iter().peekable();
+        // the held keys of a frame are separated by commas, - when none are held
+        while let Some(keys) = frames.next() {
+            let mut count = 1;
+            while frames.next_if_eq(&keys).is_some() { count += 1 }
+            let held: Vec<_> = keys.iter().map(|k| format!("{:x}", k)).collect();
+            writeln!(text, "{} {}", count, if held.is_empty() { "-".to_string() } else { held.join(",") }).unwrap();
+        }
+        text
+    }
+}
+
+pub enum Session {
+    Live,
+    Record(Movie),
+    Replay(Movie, usize),
+}
+
+impl Session {
+    // Starts over after a reset, a recording restarts with the new config
+    pub fn restart(&mut self, rom: &[u8], config: &Config) {
+        match self {
+            Session::Live => {}
+            Session::Record(movie) => *movie = Movie::new(rom, config),
+            Session::Replay(_, frame) => *frame = 0,
+        }
+    }
+
+    pub fn next_keys(&mut self, live_keys: Keys) -> Keys {
+        match self {
+            Session::Live => live_keys,
+            Session::Record(movie) => {
+                movie.record_frame(live_keys);
+                live_keys
+            }
+            Session::Replay(movie, frame) => match movie.frames.get(*frame) {
+                Some(keys) => {
+                    *frame += 1;
+                    *keys
+                }
+                None => live_keys,
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::chip8::Chip8;
+use crate::rng::Rng;
+
+pub const GLYPH_WIDTH: usize = 3;
+pub const GLYPH_HEIGHT: usize = 5;
+// Frames a message stays on screen
+pub const MESSAGE_FRAMES: u32 = 120;
+const UNKNOWN: char = '?';
+
+// 3x5 font, every row holds 3 pixels with the leftmost in the highest bit. Lowercase letters use the uppercase glyphs
+const GLYPHS: [(char, [u8; GLYPH_HEIGHT]); 53] = [
+    (' ', [0b000, 0b000, 0b000, 0b000, 0b000]),
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    (',', [0b000, 0b000, 0b000, 0b010, 0b100]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('+', [0b000, 0b010, 0b111, 0b010, 0b000]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]),
+    ('%', [0b101, 0b001, 0b010, 0b100, 0b101]),
+    ('(', [0b001, 0b010, 0b010, 0b010, 0b001]),
+    (')', [0b100, 0b010, 0b010, 0b010, 0b100]),
+    ('=', [0b000, 0b111, 0b000, 0b111, 0b000]),
+    ('>', [0b100, 0b010, 0b001, 0b010, 0b100]),
+    ('<', [0b001, 0b010, 0b100, 0b010, 0b001]),
+    ('!', [0b010, 0b010, 0b010, 0b000, 0b010]),
+    ('?', [0b110, 0b001, 0b010, 0b000, 0b010]),
+    ('_', [0b000, 0b000, 0b000, 0b000, 0b111]),
+    ('#', [0b101, 0b111, 0b101, 0b111, 0b101]),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+}
+
+#[derive(Clone, Debug)]
+pub struct Menu {
+    pub title: String,
+    pub items: Vec<String>,
+    pub selected: usize,
+}
+
+impl Menu {
+    pub fn new(title: impl Into<String>, items: Vec<String>) -> Self {
+        Menu { title: title.into(), items, selected: 0 }
+    }
+
+    pub fn up(&mut self) {
+        self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+    }
+
+    pub fn down(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len();
+    }
+}
+
+// Text drawn over the screen, the frontend keeps the state flags up to date
+#[derive(Clone, Debug, Default)]
+pub struct Osd {
+    message: Option<(String, u32)>,
+    stats: Option<(u32, u32)>,
+    pub show_stats: bool,
+    pub show_registers: bool,
+    pub paused: bool,
+    pub fast_forward: bool,
+    pub slow_motion: bool,
+    pub menu: Option<Menu>,
+}
+
+impl Osd {
+    pub fn new() -> Self {
+        Osd::default()
+    }
+
+    // Shows the message for MESSAGE_FRAMES frames, replacing the current one
+    pub fn message(&mut self, text: impl Into<String>) {
+        self.message = Some((text.into(), MESSAGE_FRAMES));
+    }
+
+    pub fn set_stats(&mut self, fps: u32, instructions_per_second: u32) {
+        self.stats = Some((fps, instructions_per_second));
+    }
+
+    // Called once per frame
+    pub fn tick(&mut self) {
+        if let Some((_, frames)) = &mut self.message {
+            *frames -= 1;
+            if *frames == 0 { self.message = None }
+        }
+    }
+
+    // Every line to draw with the corner it is stacked in, from the edge of the window inwards
+    pub fn lines<R: Rng>(&self, chip8: &Chip8<R>) -> Vec<(Corner, String)> {
+        let mut lines = Vec::new();
+        if let (true, Some((fps, ips))) = (self.show_stats, self.stats) {
+            lines.push((Corner::TopLeft, format!("{} FPS {} IPS", fps, ips)));
+        }
+        if self.paused { lines.push((Corner::TopLeft, "PAUSED".to_string())) }
+        if self.fast_forward { lines.push((Corner::TopLeft, ">> FAST FORWARD".to_string())) }
+        if self.slow_motion { lines.push((Corner::TopLeft, "SLOW MOTION".to_string())) }
+        if let Some(menu) = &self.menu {
+            lines.push((Corner::TopLeft, menu.title.clone()));
+            for (index, item) in menu.items.iter().enumerate() {
+                let marker = if index == menu.selected { ">" } else { " " };
+                lines.push((Corner::TopLeft, format!("{} {}", marker, item)));
+            }
+        }
+        if self.show_registers {
+            lines.push((Corner::TopRight, format!("PC {:04X} I {:04X}", chip8.pc(), chip8.i())));
+            for first in (0..16).step_by(4) {
+                let registers: Vec<_> = (first..first + 4).map(|x| format!("V{:X} {:02X}", x, chip8.get_register(x))).collect();
+                lines.push((Corner::TopRight, registers.join(" ")));
+            }
+        }
+        if let Some((message, _)) = &self.message { lines.push((Corner::BottomLeft, message.clone())) }
+        lines
+    }
+}
+
+pub fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    let c = c.to_ascii_uppercase();
+    GLYPHS.iter().find(|(glyph, _)| *glyph == c)
+        .or_else(|| GLYPHS.iter().find(|(glyph, _)| *glyph == UNKNOWN))
+        .map(|(_, rows)| *rows)
+        .unwrap()
+}
+
+// Width in font pixels with one pixel between the characters
+pub fn text_width(text: &str) -> usize {
+    (text.chars().count() * (GLYPH_WIDTH + 1)).saturating_sub(1)
+}
+
+// Lit pixels of the text as (x, y) in font pixels
+pub fn text_pixels(text: &str) -> impl Iterator<Item=(usize, usize)> + '_ {
+    text.chars().enumerate().flat_map(|(index, c)| {
+        let rows = glyph(c);
+        (0..GLYPH_HEIGHT).flat_map(move |y| {
+            (0..GLYPH_WIDTH).filter(move |x| rows[y] & (0b100 >> x) != 0).map(move |x| (index * (GLYPH_WIDTH + 1) + x, y))
+        })
+    })
+}
@@ -0,0 +1,142 @@
+use core::fmt;
+use core::str::FromStr;
+
+use crate::framebuffer::MAX_PLANES;
+
+pub const PALETTE_SIZE: usize = 1 << MAX_PLANES;
+
+// RGBA colour of every pixel value, 0 is the background, 1 the first plane, 2 the second plane and 3 both of them
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Palette {
+    colors: [[u8; 4]; PALETTE_SIZE],
+}
+
+impl Palette {
+    // Values without a colour of their own are drawn in the foreground colour
+    pub fn new(colors: &[[u8; 3]]) -> Self {
+        let rgba = |[r, g, b]: [u8; 3]| [r, g, b, 0xff];
+        let background = colors.first().copied().map_or([0x00, 0x00, 0x00, 0xff], rgba);
+        let foreground = colors.get(1).copied().map_or([0xff, 0xff, 0xff, 0xff], rgba);
+
+        let mut palette = [foreground; PALETTE_SIZE];
+        palette[0] = background;
+        for (entry, color) in palette.iter_mut().zip(colors.iter().copied()) {
+            *entry = rgba(color);
+        }
+        Palette { colors: palette }
+    }
+
+    pub fn colors(&self) -> &[[u8; 4]] {
+        &self.colors
+    }
+
+    pub fn color(&self, value: u8) -> [u8; 4] {
+        self.colors[value as usize % PALETTE_SIZE]
+    }
+
+    pub fn background(&self) -> [u8; 4] {
+        self.colors[0]
+    }
+
+    pub fn foreground(&self) -> [u8; 4] {
+        self.colors[1]
+    }
+
+    pub fn with_background(mut self, [r, g, b]: [u8; 3]) -> Self {
+        self.colors[0] = [r, g, b, 0xff];
+        self
+    }
+
+    pub fn with_foreground(mut self, [r, g, b]: [u8; 3]) -> Self {
+        self.colors[1] = [r, g, b, 0xff];
+        self
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Theme::default().palette()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidColor;
+
+impl fmt::Display for InvalidColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Colours are written as #rrggbb")
+    }
+}
+
+pub fn parse_color(color: &str) -> Result<[u8; 3], InvalidColor> {
+    let hex = color.trim().strip_prefix('#').unwrap_or(color.trim());
+    if hex.len() != 6 { return Err(InvalidColor); }
+    let rgb = u32::from_str_radix(hex, 16).map_err(|_| InvalidColor)?;
+    Ok([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8])
+}
+
+// Comma separated colours starting with the background
+impl FromStr for Palette {
+    type Err = InvalidColor;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut colors = [[0; 3]; PALETTE_SIZE];
+        let mut count = 0;
+        for color in s.split(',') {
+            if count == PALETTE_SIZE { return Err(InvalidColor); }
+            colors[count] = parse_color(color)?;
+            count += 1;
+        }
+        Ok(Palette::new(&colors[..count]))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Theme {
+    #[default]
+    Classic,
+    Amber,
+    GreenPhosphor,
+    Lcd,
+    Octo,
+}
+
+pub const THEMES: [Theme; 5] = [Theme::Classic, Theme::Amber, Theme::GreenPhosphor, Theme::Lcd, Theme::Octo];
+
+impl Theme {
+    pub fn palette(&self) -> Palette {
+        Palette::new(match self {
+            Theme::Classic => &[[0x00, 0x00, 0x00], [0xff, 0xff, 0xff], [0xaa, 0xaa, 0xaa], [0x55, 0x55, 0x55]],
+            Theme::Amber => &[[0x1a, 0x0f, 0x00], [0xff, 0xb0, 0x00], [0xa8, 0x6a, 0x00], [0xff, 0xe0, 0x90]],
+            Theme::GreenPhosphor => &[[0x0a, 0x1a, 0x0a], [0x33, 0xff, 0x33], [0x1f, 0x99, 0x1f], [0xb0, 0xff, 0xb0]],
+            Theme::Lcd => &[[0x9b, 0xbc, 0x0f], [0x0f, 0x38, 0x0f], [0x30, 0x62, 0x30], [0x8b, 0xac, 0x0f]],
+            // the defaults of the Octo IDE
+            Theme::Octo => &[[0x99, 0x66, 0x00], [0xff, 0xcc, 0x00], [0xff, 0x66, 0x00], [0x66, 0x22, 0x00]],
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Classic => "classic",
+            Theme::Amber => "amber",
+            Theme::GreenPhosphor => "green",
+            Theme::Lcd => "lcd",
+            Theme::Octo => "octo",
+        }
+    }
+
+    // The theme after this one, wrapping around
+    pub fn next(&self) -> Theme {
+        let index = THEMES.iter().position(|theme| theme == self).unwrap_or(0);
+        THEMES[(index + 1) % THEMES.len()]
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        THEMES.iter().copied().find(|theme| theme.name() == s).ok_or(())
+    }
+}
@@ -0,0 +1,105 @@
+use core::str::FromStr;
+
+use crate::framebuffer::{Framebuffer, MAX_HEIGHT, MAX_WIDTH, Resolution};
+
+const FULL: u8 = 0xff;
+pub const DEFAULT_RETENTION: u8 = 60;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Persistence {
+    // Pixels show exactly what is in the framebuffer
+    #[default]
+    Off,
+    // Unlit pixels fade out keeping the given percentage of their brightness every frame like CRT phosphor
+    Phosphor(u8),
+    // Pixels lit in the current or the last frame are shown, hiding sprites erased and redrawn every frame
+    Deflicker,
+}
+
+// off, deflicker, phosphor or phosphor:<percent kept per frame>
+impl FromStr for Persistence {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "off" => Ok(Persistence::Off),
+            None if s == "deflicker" => Ok(Persistence::Deflicker),
+            None if s == "phosphor" => Ok(Persistence::Phosphor(DEFAULT_RETENTION)),
+            Some(("phosphor", retention)) => match retention.parse() {
+                Ok(retention @ 0..=100) => Ok(Persistence::Phosphor(retention)),
+                _ => Err(()),
+            },
+            _ => Err(()),
+        }
+    }
+}
+
+// Brightness and value of every pixel as shown, updated once per frame from the framebuffer
+#[derive(Clone)]
+pub struct PersistenceFilter {
+    mode: Persistence,
+    resolution: Resolution,
+    values: [u8; MAX_WIDTH * MAX_HEIGHT],
+    intensities: [u8; MAX_WIDTH * MAX_HEIGHT],
+    previous: [u8; MAX_WIDTH * MAX_HEIGHT],
+}
+
+impl PersistenceFilter {
+    pub fn new(mode: Persistence) -> Self {
+        PersistenceFilter {
+            mode,
+            resolution: Resolution::default(),
+            values: [0; MAX_WIDTH * MAX_HEIGHT],
+            intensities: [0; MAX_WIDTH * MAX_HEIGHT],
+            previous: [0; MAX_WIDTH * MAX_HEIGHT],
+        }
+    }
+
+    pub fn mode(&self) -> Persistence {
+        self.mode
+    }
+
+    // Returns whether any pixel is shown differently than before
+    pub fn update(&mut self, framebuffer: &Framebuffer) -> bool {
+        let mut changed = framebuffer.resolution() != self.resolution;
+        if changed {
+            self.resolution = framebuffer.resolution();
+            self.intensities.fill(0);
+            self.previous.fill(0);
+        }
+
+        let pixels = framebuffer.rows().flatten().zip(self.values.iter_mut().zip(self.intensities.iter_mut()).zip(self.previous.iter_mut()));
+        for (current, ((value, intensity), previous)) in pixels {
+            let shown = (*value, *intensity);
+            match self.mode {
+                Persistence::Off => (*value, *intensity) = (*current, FULL),
+                Persistence::Deflicker => (*value, *intensity) = (*current | *previous, FULL),
+                Persistence::Phosphor(_) if *current != 0 => (*value, *intensity) = (*current, FULL),
+                Persistence::Phosphor(retention) => *intensity = (*intensity as u16 * retention as u16 / 100) as u8,
+            }
+            *previous = *current;
+            changed |= shown != (*value, *intensity);
+        }
+        changed
+    }
+
+    // Every pixel as (x, y, value, brightness) row by row, a pixel fully faded out has value 0
+    pub fn pixels(&self) -> impl Iterator<Item=(usize, usize, u8, u8)> + '_ {
+        let width = self.resolution.width();
+        let count = width * self.resolution.height();
+        self.values[..count].iter().zip(&self.intensities[..count]).enumerate().map(move |(index, (value, intensity))| {
+            let value = if *intensity == 0 { 0 } else { *value };
+            (index % width, index / width, value, *intensity)
+        })
+    }
+}
+
+// Mixes the background towards the pixel colour by its brightness
+pub fn fade(background: [u8; 3], color: [u8; 3], intensity: u8) -> [u8; 3] {
+    let mut faded = [0; 3];
+    for (channel, (background, color)) in faded.iter_mut().zip(background.iter().zip(color)) {
+        *channel = ((*background as u16 * (FULL - intensity) as u16 + color as u16 * intensity as u16) / FULL as u16) as u8;
+    }
+    faded
+}
@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::chip8::{Chip8, Config};
+use crate::chip8_runner::{HeadlessRunner, StopReason};
+use crate::quirks::{Platform, Quirks};
+use crate::to_u16;
+
+const PROGRAM_POINTER: u16 = 0x200;
+const LOOKBEHIND: u16 = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Suggestion {
+    Platform(Platform),
+    Quirk(&'static str, bool),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Evidence {
+    pub addr: u16,
+    pub opcode: u16,
+    pub reason: String,
+    pub suggestion: Suggestion,
+}
+
+pub struct DetectionReport {
+    pub platform: Platform,
+    pub quirks: Quirks,
+    pub evidence: Vec<Evidence>,
+}
+
+impl DetectionReport {
+    pub fn config(&self) -> Config {
+        Config::ch8().with_platform(self.platform).with_quirks(self.quirks.clone())
+    }
+}
+
+impl fmt::Display for DetectionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Detected platform {:?}", self.platform)?;
+        if self.evidence.is_empty() { writeln!(f, "  no evidence found, using defaults")? }
+        for evidence in &self.evidence {
+            let suggestion = match evidence.suggestion {
+                Suggestion::Platform(platform) => format!("platform {:?}", platform),
+                Suggestion::Quirk(name, value) => format!("{} = {}", name, value),
+            };
+            writeln!(f, "  {:#05x} {:04x}: {} -> {}", evidence.addr, evidence.opcode, evidence.reason, suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+// Static scan of the reachable code. Data mixed into the rom is skipped by following the control flow from
+// the entry point instead of decoding every word.
+pub fn detect(rom: &[u8]) -> DetectionReport {
+    let code = reachable_code(rom);
+    let mut evidence = Vec::new();
+
+    for (&addr, &opcode) in &code {
+        if let Some(platform) = platform_only_opcode(opcode) {
+            evidence.push(Evidence { addr, opcode, reason: format!("{:?} only instruction", platform), suggestion: Suggestion::Platform(platform) });
+        }
+    }
+
+    for (&addr, &opcode) in &code {
+        let (x, y) = (((opcode >> 8) & 0xf) as u8, ((opcode >> 4) & 0xf) as u8);
+        match opcode & 0xf00f {
+            0x8006 | 0x800e if x != y => evidence.push(Evidence {
+                addr,
+                opcode,
+                reason: "shift reads Vy into Vx".to_string(),
+                suggestion: Suggestion::Quirk("shifting", false),
+            }),
+            _ => {}
+        }
+
+        match opcode & 0xf0ff {
+            0xf055 | 0xf065 => {
+                if let Some(next) = reuses_i(&code, addr) {
+                    evidence.push(Evidence {
+                        addr,
+                        opcode,
+                        reason: format!("I is used again at {:#05x} without being set", next),
+                        suggestion: Suggestion::Quirk("memory", false),
+                    });
+                }
+            }
+            _ => {}
+        }
+
+        if opcode & 0xf000 == 0xb000 && x != 0 {
+            if let Some(jumping) = jump_register(&code, addr, x) {
+                let reason = if jumping { format!("V{:X} is set before the jump", x) } else { "V0 is set before the jump".to_string() };
+                evidence.push(Evidence { addr, opcode, reason, suggestion: Suggestion::Quirk("jumping", jumping) });
+            }
+        }
+    }
+
+    let platform = evidence.iter()
+        .filter_map(|evidence| match evidence.suggestion {
+            Suggestion::Platform(platform) => Some(platform),
+            _ => None,
+        })
+        .max_by_key(|platform| match platform {
+            Platform::CosmacVip => 0,
+            Platform::Chip8E => 1,
+            Platform::Chip8X => 2,
+            Platform::Chip48 => 3,
+            Platform::SuperChip10 => 4,
+            Platform::SuperChip11 => 5,
+            Platform::SuperChipModern => 6,
+            Platform::MegaChip => 7,
+            Platform::XoChip => 8,
+        })
+        .unwrap_or(Platform::CosmacVip);
+
+    let mut quirks = platform.quirks();
+    for evidence in &evidence {
+        if let Suggestion::Quirk(name, value) = evidence.suggestion { quirks.set(name, value); }
+    }
+
+    DetectionReport { platform, quirks, evidence }
+}
+
+// Runs the rom shortly with every platform profile, a profile that crashes while another one doesn't is ruled out
+pub fn detect_with_execution(rom: &[u8], frames: u64) -> DetectionReport {
+    let mut report = detect(rom);
+    let crash = |platform: Platform, quirks: &Quirks| {
+        let mut chip8 = match Chip8::new(rom, Config::ch8().with_platform(platform).with_quirks(quirks.clone()).with_seed(0)) {
+            Ok(chip8) => chip8,
+            Err(err) => return Some(err),
+        };
+        match HeadlessRunner::new().max_frames(frames).run(&mut chip8).reason {
+            StopReason::Error(err) => Some(err),
+            _ => None,
+        }
+    };
+
+    let Some(err) = crash(report.platform, &report.quirks) else { return report };
+    let survivor = [Platform::CosmacVip, Platform::SuperChipModern, Platform::XoChip].into_iter()
+        .filter(|platform| *platform != report.platform)
+        .find(|platform| crash(*platform, &platform.quirks()).is_none());
+
+    if let Some(survivor) = survivor {
+        report.evidence.push(Evidence {
+            addr: PROGRAM_POINTER,
+            opcode: 0,
+            reason: format!("{:?} profile stopped with '{}' within {} frames", report.platform, err, frames),
+            suggestion: Suggestion::Platform(survivor),
+        });
+        report.platform = survivor;
+        report.quirks = survivor.quirks();
+    }
+
+    report
+}
+
+fn reachable_code(rom: &[u8]) -> BTreeMap<u16, u16> {
+    let end = PROGRAM_POINTER as usize + rom.len();
+    let fetch = |addr: u16| -> Option<u16> {
+        let i = (addr as usize).checked_sub(PROGRAM_POINTER as usize)?;
+        (addr as usize + 1 < end).then(|| to_u16!(rom[i], rom[i + 1]))
+    };
+
+    let mut code = BTreeMap::new();
+    let mut pending = vec![PROGRAM_POINTER];
+    while let Some(addr) = pending.pop() {
+        if code.contains_key(&addr) { continue; }
+        let Some(opcode) = fetch(addr) else { continue };
+        code.insert(addr, opcode);
+
+        // the words after the last one in memory don't exist
+        let next = |offset: u16| addr.checked_add(offset);
+        let nnn = opcode & 0x0fff;
+        match opcode >> 12 {
+            0x0 if opcode == 0x00ee || opcode == 0x00fd || opcode == 0x00ed => {}
+            // CHIP-8E skip
+            0x0 if opcode == 0x0188 => pending.extend(next(4)),
+            0x1 => pending.push(nnn),
+            0x2 => pending.extend([Some(nnn), next(2)].into_iter().flatten()),
+            0x3 | 0x4 | 0x5 | 0x9 => pending.extend([next(2), next(4)].into_iter().flatten()),
+            0xb => {}
+            0xe if matches!(opcode & 0xff, 0x9e | 0xa1) => pending.extend([next(2), next(4)].into_iter().flatten()),
+            // XO-CHIP long load, the following word is data
+            0xf if opcode == 0xf000 => pending.extend(next(4)),
+            _ => pending.extend(next(2)),
+        }
+    }
+
+    code
+}
+
+fn platform_only_opcode(opcode: u16) -> Option<Platform> {
+    match opcode {
+        0x00fb..=0x00ff => Some(Platform::SuperChipModern),
+        _ if opcode & 0xfff0 == 0x00c0 => Some(Platform::SuperChipModern),
+        _ if opcode & 0xf00f == 0xd000 => Some(Platform::SuperChipModern),
+        _ if matches!(opcode & 0xf0ff, 0xf030 | 0xf075 | 0xf085) => Some(Platform::SuperChipModern),
+        0x02a0 => Some(Platform::Chip8X),
+        0x0010 | 0x0011 => Some(Platform::MegaChip),
+        0x00ed | 0x00f2 | 0x0151 | 0x0188 => Some(Platform::Chip8E),
+        0xf000 | 0xf002 => Some(Platform::XoChip),
+        _ if opcode & 0xfff0 == 0x00d0 => Some(Platform::XoChip),
+        _ if matches!(opcode & 0xf00f, 0x5002 | 0x5003) => Some(Platform::XoChip),
+        _ if matches!(opcode & 0xf0ff, 0xf001 | 0xf03a) => Some(Platform::XoChip),
+        _ => None,
+    }
+}
+
+// Whether the next instruction touching I after FX55/FX65 relies on the old value
+fn reuses_i(code: &BTreeMap<u16, u16>, addr: u16) -> Option<u16> {
+    let mut next = addr.checked_add(2)?;
+    while let Some(&opcode) = code.get(&next) {
+        match opcode >> 12 {
+            0x1 | 0x2 | 0xb => return None,
+            0xa => return None,
+            0xd => return Some(next),
+            0xf => match opcode & 0xff {
+                0x1e | 0x29 | 0x30 => return None,
+                0x33 | 0x55 | 0x65 => return Some(next),
+                _ => {}
+            },
+            0x0 if opcode == 0x00ee => return None,
+            _ => {}
+        }
+        next = next.checked_add(2)?;
+    }
+    None
+}
+
+// Looks at the few instructions before BXNN for the register that holds the offset
+fn jump_register(code: &BTreeMap<u16, u16>, addr: u16, x: u8) -> Option<bool> {
+    (1..=LOOKBEHIND)
+        .filter_map(|back| addr.checked_sub(back * 2).and_then(|prev| code.get(&prev)))
+        .find_map(|opcode| {
+            let target = ((opcode >> 8) & 0xf) as u8;
+            let writes = matches!(opcode >> 12, 0x6 | 0x7 | 0x8 | 0xc) || matches!(opcode & 0xf0ff, 0xf007 | 0xf00a | 0xf065);
+            match (writes, target) {
+                (true, 0) => Some(false),
+                (true, t) if t == x => Some(true),
+                _ => None,
+            }
+        })
+}
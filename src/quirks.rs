@@ -1,50 +1,396 @@
-use crate::chip8::{CH8_HEIGHT, CH8_WIDTH, VBLank};
+use core::fmt;
+use core::str::FromStr;
+
+use crate::bit_ops::get_bit_at;
+use crate::chip8::VBLank;
+use crate::framebuffer::Resolution;
 use crate::registers::Registers;
 
+// COSMAC VIP
 pub const CH8_QUIRKS: Quirks = Quirks {
     vf_reset: true,
     memory: true,
+    memory_increment_by_x: false,
     shifting: false,
     jumping: false,
     display_wait: true,
-    clipping: true,
+    clipping_x: true,
+    clipping_y: true,
+    i_overflow: false,
+    dxy0_8x16: false,
+    dxy0_16x16: false,
+    half_pixel_scroll: false,
+    clear_wait: false,
+};
+
+// CHIP-8X, the VIP interpreter for the VP-590 colour board
+pub const CHIP8X_QUIRKS: Quirks = CH8_QUIRKS;
+
+// CHIP-8E, the extended VIP interpreter
+pub const CHIP8E_QUIRKS: Quirks = CH8_QUIRKS;
+
+// CHIP-48 on the HP 48
+pub const CHIP48_QUIRKS: Quirks = Quirks {
+    vf_reset: false,
+    memory: true,
+    memory_increment_by_x: true,
+    shifting: true,
+    jumping: true,
+    display_wait: false,
+    clipping_x: true,
+    clipping_y: true,
+    i_overflow: false,
+    dxy0_8x16: false,
+    dxy0_16x16: false,
+    half_pixel_scroll: false,
+    clear_wait: false,
+};
+
+// SUPER-CHIP 1.0 on the HP 48
+pub const SCHIP10_QUIRKS: Quirks = Quirks {
+    vf_reset: false,
+    memory: true,
+    memory_increment_by_x: true,
+    shifting: true,
+    jumping: true,
+    display_wait: true,
+    clipping_x: true,
+    clipping_y: true,
+    i_overflow: false,
+    dxy0_8x16: true,
+    dxy0_16x16: false,
+    half_pixel_scroll: true,
+    clear_wait: false,
 };
 
+// SUPER-CHIP 1.1 on the HP 48
+pub const SCHIP11_QUIRKS: Quirks = Quirks {
+    vf_reset: false,
+    memory: false,
+    memory_increment_by_x: false,
+    shifting: true,
+    jumping: true,
+    display_wait: true,
+    clipping_x: true,
+    clipping_y: true,
+    i_overflow: false,
+    dxy0_8x16: true,
+    dxy0_16x16: false,
+    half_pixel_scroll: true,
+    clear_wait: false,
+};
+
+// Modern SUPER-CHIP as implemented by Octo (SCHIPC)
 pub const SCHIP_QUIRKS: Quirks = Quirks {
     vf_reset: false,
     memory: false,
+    memory_increment_by_x: false,
     shifting: true,
     jumping: true,
     display_wait: false,
-    clipping: true,
+    clipping_x: true,
+    clipping_y: true,
+    i_overflow: false,
+    dxy0_8x16: false,
+    dxy0_16x16: true,
+    half_pixel_scroll: false,
+    clear_wait: false,
 };
 
+// MegaChip, built on SUPER-CHIP 1.1 for the PC
+pub const MEGACHIP_QUIRKS: Quirks = Quirks {
+    vf_reset: false,
+    memory: false,
+    memory_increment_by_x: false,
+    shifting: true,
+    jumping: true,
+    display_wait: false,
+    clipping_x: true,
+    clipping_y: true,
+    i_overflow: false,
+    dxy0_8x16: false,
+    dxy0_16x16: true,
+    half_pixel_scroll: false,
+    clear_wait: false,
+};
+
+// XO-CHIP
 pub const XOCHIP_QUIRKS: Quirks = Quirks {
     vf_reset: false,
     memory: true,
+    memory_increment_by_x: false,
     shifting: false,
     jumping: false,
     display_wait: false,
-    clipping: false,
+    clipping_x: false,
+    clipping_y: false,
+    i_overflow: false,
+    dxy0_8x16: false,
+    dxy0_16x16: true,
+    half_pixel_scroll: false,
+    clear_wait: false,
 };
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Platform {
+    CosmacVip,
+    Chip8X,
+    Chip8E,
+    Chip48,
+    #[cfg(feature = "schip")]
+    SuperChip10,
+    #[cfg(feature = "schip")]
+    SuperChip11,
+    #[cfg(feature = "schip")]
+    SuperChipModern,
+    // needs std for its memory and colours
+    #[cfg(feature = "std")]
+    MegaChip,
+    #[cfg(feature = "xochip")]
+    XoChip,
+}
+
+// The platforms this build runs, only those whose screen and memory fit
+pub const PLATFORMS: &[Platform] = &[
+    Platform::CosmacVip,
+    Platform::Chip8X,
+    Platform::Chip8E,
+    Platform::Chip48,
+    #[cfg(feature = "schip")]
+    Platform::SuperChip10,
+    #[cfg(feature = "schip")]
+    Platform::SuperChip11,
+    #[cfg(feature = "schip")]
+    Platform::SuperChipModern,
+    #[cfg(feature = "std")]
+    Platform::MegaChip,
+    #[cfg(feature = "xochip")]
+    Platform::XoChip,
+];
+
+impl Platform {
+    pub fn quirks(&self) -> Quirks {
+        match self {
+            Platform::CosmacVip => CH8_QUIRKS,
+            Platform::Chip8X => CHIP8X_QUIRKS,
+            Platform::Chip8E => CHIP8E_QUIRKS,
+            Platform::Chip48 => CHIP48_QUIRKS,
+            #[cfg(feature = "schip")]
+            Platform::SuperChip10 => SCHIP10_QUIRKS,
+            #[cfg(feature = "schip")]
+            Platform::SuperChip11 => SCHIP11_QUIRKS,
+            #[cfg(feature = "schip")]
+            Platform::SuperChipModern => SCHIP_QUIRKS,
+            #[cfg(feature = "std")]
+            Platform::MegaChip => MEGACHIP_QUIRKS,
+            #[cfg(feature = "xochip")]
+            Platform::XoChip => XOCHIP_QUIRKS,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::CosmacVip => "chip8",
+            Platform::Chip8X => "chip8x",
+            Platform::Chip8E => "chip8e",
+            Platform::Chip48 => "chip48",
+            #[cfg(feature = "schip")]
+            Platform::SuperChip10 => "schip1.0",
+            #[cfg(feature = "schip")]
+            Platform::SuperChip11 => "schip1.1",
+            #[cfg(feature = "schip")]
+            Platform::SuperChipModern => "schip",
+            #[cfg(feature = "std")]
+            Platform::MegaChip => "megachip",
+            #[cfg(feature = "xochip")]
+            Platform::XoChip => "xochip",
+        }
+    }
+
+    // SUPER-CHIP and its successors switch between 64x32 and 128x64 with 00FE and 00FF
+    pub fn has_hires(&self) -> bool {
+        match self {
+            #[cfg(feature = "schip")]
+            Platform::SuperChip10 | Platform::SuperChip11 | Platform::SuperChipModern => true,
+            #[cfg(feature = "std")]
+            Platform::MegaChip => true,
+            #[cfg(feature = "xochip")]
+            Platform::XoChip => true,
+            _ => false,
+        }
+    }
+
+    // XO-CHIP addresses 64K with its 16 bit I, the others the 4K of the VIP
+    pub fn memory_size(&self) -> usize {
+        match self {
+            #[cfg(feature = "xochip")]
+            Platform::XoChip => 0x10000,
+            _ => 0x1000,
+        }
+    }
+
+    // CHIP-8X needs more room for its interpreter
+    pub fn program_start(&self) -> u16 {
+        match self {
+            Platform::Chip8X => 0x300,
+            _ => 0x200,
+        }
+    }
+}
+
+impl FromStr for Platform {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chip8" | "vip" => Ok(Platform::CosmacVip),
+            "chip8x" => Ok(Platform::Chip8X),
+            "chip8e" => Ok(Platform::Chip8E),
+            "chip48" => Ok(Platform::Chip48),
+            #[cfg(feature = "schip")]
+            "schip1.0" => Ok(Platform::SuperChip10),
+            #[cfg(feature = "schip")]
+            "schip1.1" => Ok(Platform::SuperChip11),
+            #[cfg(feature = "schip")]
+            "schip" | "schipc" => Ok(Platform::SuperChipModern),
+            #[cfg(feature = "std")]
+            "megachip" | "megachip8" => Ok(Platform::MegaChip),
+            #[cfg(feature = "xochip")]
+            "xochip" => Ok(Platform::XoChip),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct UnknownQuirk;
+
+impl fmt::Display for UnknownQuirk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown quirk or platform")
+    }
+}
+
+// Bit order of to_bits, also used for the names in the compact string form
+pub const QUIRK_NAMES: [&str; 13] = [
+    "vf_reset", "memory", "shifting", "jumping", "display_wait", "clipping_x", "clipping_y", "memory_increment_by_x",
+    "i_overflow", "dxy0_8x16", "dxy0_16x16", "half_pixel_scroll", "clear_wait",
+];
 
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default, deny_unknown_fields))]
 pub struct Quirks {
+    // 8XY1, 8XY2 and 8XY3 reset VF to 0
     vf_reset: bool,
+    // FX55 and FX65 increment I
     memory: bool,
+    // FX55 and FX65 increment I by X instead of X + 1, needs `memory`
+    memory_increment_by_x: bool,
+    // 8XY6 and 8XYE shift VX in place instead of copying VY first
     shifting: bool,
+    // BXNN jumps to XNN + VX instead of NNN + V0
     jumping: bool,
+    // DXYN waits for the vertical blank interrupt
     display_wait: bool,
-    clipping: bool,
+    // sprites are clipped at the right edge instead of wrapping around
+    clipping_x: bool,
+    // sprites are clipped at the bottom edge instead of wrapping around
+    clipping_y: bool,
+    // FX1E sets VF when I goes past 0xFFF
+    i_overflow: bool,
+    // DXY0 draws an 8x16 sprite in lores, `dxy0_16x16` takes precedence and DXY0 draws nothing without either
+    dxy0_8x16: bool,
+    // DXY0 draws a 16x16 sprite in lores
+    dxy0_16x16: bool,
+    // 00CN, 00FB and 00FC scroll half the distance in lores
+    half_pixel_scroll: bool,
+    // 00E0 waits for the vertical blank interrupt
+    clear_wait: bool,
 }
 
 impl Quirks {
+    pub fn builder() -> QuirksBuilder {
+        QuirksBuilder::default()
+    }
+
+    pub fn to_builder(&self) -> QuirksBuilder {
+        QuirksBuilder { quirks: self.clone() }
+    }
+
+    pub fn to_bits(&self) -> u32 {
+        [
+            self.vf_reset, self.memory, self.shifting, self.jumping, self.display_wait, self.clipping_x,
+            self.clipping_y, self.memory_increment_by_x, self.i_overflow, self.dxy0_8x16, self.dxy0_16x16,
+            self.half_pixel_scroll, self.clear_wait,
+        ]
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, quirk)| bits | (*quirk as u32) << i)
+    }
+
+    pub fn from_bits(bits: u32) -> Self {
+        Quirks {
+            vf_reset: get_bit_at(bits, 0),
+            memory: get_bit_at(bits, 1),
+            shifting: get_bit_at(bits, 2),
+            jumping: get_bit_at(bits, 3),
+            display_wait: get_bit_at(bits, 4),
+            clipping_x: get_bit_at(bits, 5),
+            clipping_y: get_bit_at(bits, 6),
+            memory_increment_by_x: get_bit_at(bits, 7),
+            i_overflow: get_bit_at(bits, 8),
+            dxy0_8x16: get_bit_at(bits, 9),
+            dxy0_16x16: get_bit_at(bits, 10),
+            half_pixel_scroll: get_bit_at(bits, 11),
+            clear_wait: get_bit_at(bits, 12),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<bool> {
+        let i = QUIRK_NAMES.iter().position(|quirk| *quirk == name)?;
+        Some(get_bit_at(self.to_bits(), i as u32))
+    }
+
+    pub fn set(&mut self, name: &str, value: bool) -> bool {
+        let Some(i) = QUIRK_NAMES.iter().position(|quirk| *quirk == name) else { return false };
+        *self = Quirks::from_bits(self.to_bits() & !(1 << i) | (value as u32) << i);
+        true
+    }
+
+    // Applies a compact list like "schip,-shifting,i_overflow" on top of the current quirks, a platform name
+    // replaces all quirks with its profile
+    pub fn apply(&mut self, list: &str) -> Result<(), UnknownQuirk> {
+        for item in list.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            if let Ok(platform) = item.parse::<Platform>() {
+                *self = platform.quirks();
+                continue;
+            }
+
+            let applied = match item.strip_prefix('-') {
+                Some(name) => self.set(name, false),
+                None => self.set(item, true),
+            };
+            if !applied { return Err(UnknownQuirk) }
+        }
+        Ok(())
+    }
+
+    // (name, value in self, value in other) for every quirk that differs
+    pub fn diff(&self, other: &Quirks) -> impl Iterator<Item = (&'static str, bool, bool)> {
+        let (bits, other_bits) = (self.to_bits(), other.to_bits());
+        QUIRK_NAMES.iter()
+            .enumerate()
+            .filter(move |(i, _)| get_bit_at(bits ^ other_bits, *i as u32))
+            .map(move |(i, name)| (*name, get_bit_at(bits, i as u32), get_bit_at(other_bits, i as u32)))
+    }
+
     pub fn vf_reset(&self, registers: &mut Registers) {
         if self.vf_reset { registers.set_vf(0) }
     }
 
     pub fn memory(&self, registers: &mut Registers, x: u16) {
-        if self.memory { registers.i += x + 1 }
+        if self.memory { registers.i = registers.i.wrapping_add(if self.memory_increment_by_x { x } else { x + 1 }) }
     }
 
     pub fn shifting(&self, registers: &mut Registers, x: u8, y: u8) {
@@ -56,28 +402,106 @@ impl Quirks {
     }
 
     pub fn display_wait(&self, vblank: &mut VBLank) -> bool {
-        if !self.display_wait { return false; }
+        self.display_wait && wait_for_vblank(vblank)
+    }
 
-        match vblank {
-            VBLank::WaitForDraw => {
-                *vblank = VBLank::WaitForInterrupt;
-                true
-            }
-            VBLank::WaitForInterrupt => {
-                true
-            }
-            VBLank::Free => {
-                *vblank = VBLank::WaitForDraw;
-                false
-            }
-        }
+    pub fn clear_wait(&self, vblank: &mut VBLank) -> bool {
+        self.clear_wait && wait_for_vblank(vblank)
+    }
+
+    pub fn waits_for_interrupt(&self, opcode: u16) -> bool {
+        (self.display_wait && opcode & 0xf000 == 0xd000) || (self.clear_wait && opcode == 0x00e0)
     }
 
-    pub fn clipping(&self, vx: u8, pix: u8, vy: u8, row: u8) -> (u8, u8) {
-        if self.clipping {
-            (vx + pix, vy + row)
+    pub fn i_overflow(&self, registers: &mut Registers) {
+        if self.i_overflow { registers.set_vf((registers.i > 0xfff) as u8) }
+    }
+
+    pub fn lores_big_sprite(&self) -> Option<(u8, u8)> {
+        if self.dxy0_16x16 {
+            Some((16, 16))
+        } else if self.dxy0_8x16 {
+            Some((8, 16))
         } else {
-            ((vx + pix) % CH8_WIDTH, (vy + row) % CH8_HEIGHT)
+            None
         }
     }
-}
\ No newline at end of file
+
+    pub fn half_pixel_scroll(&self) -> bool {
+        self.half_pixel_scroll
+    }
+
+    pub fn clipping(&self, x: usize, y: usize, resolution: Resolution) -> (usize, usize) {
+        let x = if self.clipping_x { x } else { x % resolution.width() };
+        let y = if self.clipping_y { y } else { y % resolution.height() };
+        (x, y)
+    }
+}
+
+fn wait_for_vblank(vblank: &mut VBLank) -> bool {
+    match vblank {
+        VBLank::WaitForDraw => {
+            *vblank = VBLank::WaitForInterrupt;
+            true
+        }
+        VBLank::WaitForInterrupt => {
+            true
+        }
+        VBLank::Free => {
+            *vblank = VBLank::WaitForDraw;
+            false
+        }
+    }
+}
+
+impl FromStr for Quirks {
+    type Err = UnknownQuirk;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut quirks = Quirks::default();
+        quirks.apply(s)?;
+        Ok(quirks)
+    }
+}
+
+impl fmt::Display for Quirks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, name) in QUIRK_NAMES.iter().enumerate() {
+            if i > 0 { write!(f, ",")? }
+            if !get_bit_at(self.to_bits(), i as u32) { write!(f, "-")? }
+            write!(f, "{}", name)?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! quirk_setters {
+    ($($name:ident),*) => {
+        $(
+            pub fn $name(mut self, value: bool) -> Self {
+                self.quirks.$name = value;
+                self
+            }
+        )*
+    };
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct QuirksBuilder {
+    quirks: Quirks,
+}
+
+impl QuirksBuilder {
+    quirk_setters!(
+        vf_reset, memory, memory_increment_by_x, shifting, jumping, display_wait, clipping_x, clipping_y, i_overflow,
+        dxy0_8x16, dxy0_16x16, half_pixel_scroll, clear_wait
+    );
+
+    pub fn clipping(self, value: bool) -> Self {
+        self.clipping_x(value).clipping_y(value)
+    }
+
+    pub fn build(self) -> Quirks {
+        self.quirks
+    }
+}
@@ -1,5 +1,7 @@
 use num::ToPrimitive;
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Registers {
     registers: [u8; 16],
     pub i: u16,
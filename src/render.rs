@@ -0,0 +1,27 @@
+pub const DEFAULT_SCALE: u32 = 20;
+pub const MAX_SCALE: u32 = 40;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WindowSettings {
+    // Window pixels per lores pixel, the image keeps its size when switching to hires
+    pub scale: u32,
+    pub fullscreen: bool,
+    // Lines between the pixels
+    pub grid: bool,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        WindowSettings { scale: DEFAULT_SCALE, fullscreen: false, grid: false }
+    }
+}
+
+// Where a screen of the given size goes in the window: scaled by the biggest integer factor that fits and centered,
+// returned as (x, y, width, height)
+pub fn letterbox((window_width, window_height): (u32, u32), (width, height): (u32, u32)) -> (i32, i32, u32, u32) {
+    let scale = (window_width / width).min(window_height / height).max(1);
+    let (scaled_width, scaled_height) = (width * scale, height * scale);
+    let x = (window_width as i32 - scaled_width as i32) / 2;
+    let y = (window_height as i32 - scaled_height as i32) / 2;
+    (x, y, scaled_width, scaled_height)
+}
@@ -0,0 +1,111 @@
+pub trait Rng {
+    fn next_u8(&mut self) -> u8;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RngMode {
+    XorShift,
+    CosmacVip,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DefaultRng {
+    XorShift(XorShiftRng),
+    CosmacVip(VipRng),
+}
+
+impl DefaultRng {
+    pub fn new(mode: RngMode, seed: u32) -> Self {
+        match mode {
+            RngMode::XorShift => DefaultRng::XorShift(XorShiftRng::new(seed)),
+            RngMode::CosmacVip => DefaultRng::CosmacVip(VipRng::new(seed)),
+        }
+    }
+}
+
+impl Rng for DefaultRng {
+    fn next_u8(&mut self) -> u8 {
+        match self {
+            DefaultRng::XorShift(rng) => rng.next_u8(),
+            DefaultRng::CosmacVip(rng) => rng.next_u8(),
+        }
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct XorShiftRng {
+    state: u32,
+}
+
+impl XorShiftRng {
+    pub fn new(seed: u32) -> Self {
+        // xorshift gets stuck on a zero state
+        XorShiftRng { state: if seed == 0 { 0x2545_f491 } else { seed } }
+    }
+}
+
+impl Default for XorShiftRng {
+    fn default() -> Self {
+        XorShiftRng::new(0)
+    }
+}
+
+impl Rng for XorShiftRng {
+    fn next_u8(&mut self) -> u8 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        (x >> 24) as u8
+    }
+}
+
+// The CXNN routine of the VIP interpreter. R9 is incremented, its low byte picks a byte of the interpreter's own
+// code page which is added to its high byte, and the sum rotated right through the carry is added to the sum again.
+// That becomes the new high byte and the random number. The interpreter code isn't shipped, so unless a page is
+// given its bytes are derived from the seed.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VipRng {
+    seed: u32,
+    // a page given with with_page isn't part of save states, the loaded rng reads the seeded one
+    #[cfg_attr(feature = "serde", serde(skip))]
+    page: Option<&'static [u8; 256]>,
+    // R9.0 and R9.1
+    pointer: u8,
+    value: u8,
+}
+
+impl VipRng {
+    // The seed sets R9 from its low 16 bits
+    pub fn new(seed: u32) -> Self {
+        VipRng { seed, page: None, pointer: seed as u8, value: (seed >> 8) as u8 }
+    }
+
+    pub fn with_page(seed: u32, page: &'static [u8; 256]) -> Self {
+        VipRng { page: Some(page), ..VipRng::new(seed) }
+    }
+
+    fn page_byte(&self) -> u8 {
+        match self.page {
+            Some(page) => page[self.pointer as usize],
+            None => XorShiftRng::new(self.seed ^ ((self.pointer as u32) << 16 | self.pointer as u32)).next_u8(),
+        }
+    }
+}
+
+impl Rng for VipRng {
+    fn next_u8(&mut self) -> u8 {
+        // INC R9 carries into the high byte
+        let r9 = u16::from_be_bytes([self.value, self.pointer]).wrapping_add(1);
+        [self.value, self.pointer] = r9.to_be_bytes();
+        // GHI R9, ADD M(RE), SHRC, ADD M(R6)
+        let (sum, carry) = self.value.overflowing_add(self.page_byte());
+        self.value = (sum >> 1 | (carry as u8) << 7).wrapping_add(sum);
+        self.value
+    }
+}
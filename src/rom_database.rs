@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::chip8::Config;
+use crate::palette::{Palette, parse_color};
+use crate::quirks::{Platform, Quirks};
+
+// Subset of the community CHIP-8 database (programs.json) with the roms of the tests, scripts/update-database.sh
+// replaces it with the full file, which can also be loaded with RomDatabase::load
+const BUILTIN_DATABASE: &str = include_str!("../data/programs.json");
+
+#[derive(Clone, Debug)]
+pub struct RomInfo {
+    pub title: String,
+    pub platform: Platform,
+    pub quirks: Quirks,
+    // hybridVIP roms call CDP1802 machine code routines
+    pub cdp1802: bool,
+    pub tickrate: Option<u32>,
+    pub colors: Vec<[u8; 3]>,
+    pub keys: Vec<(String, u8)>,
+}
+
+impl RomInfo {
+    pub fn config(&self) -> Config {
+        let mut config = Config::ch8().with_platform(self.platform).with_quirks(self.quirks.clone()).with_cdp1802(self.cdp1802);
+        if let Some(tickrate) = self.tickrate { config = config.with_cycles_per_frame(tickrate) }
+        if !self.colors.is_empty() { config = config.with_palette(Palette::new(&self.colors)) }
+        config
+    }
+}
+
+pub struct RomDatabase {
+    roms: HashMap<String, RomInfo>,
+}
+
+impl RomDatabase {
+    pub fn builtin() -> Self {
+        RomDatabase::parse(BUILTIN_DATABASE).expect("Builtin rom database is invalid")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|err| format!("Error encountered while loading rom database from path {} : {}", path.display(), err))?;
+        RomDatabase::parse(&json)
+    }
+
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let programs: Value = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        let programs = programs.as_array().ok_or("Rom database must be a list of programs")?;
+
+        let mut roms = HashMap::new();
+        for program in programs {
+            let title = program["title"].as_str().unwrap_or("Unknown").to_string();
+            for (hash, rom) in program["roms"].as_object().into_iter().flatten() {
+                if let Some(info) = parse_rom(&title, rom) {
+                    roms.insert(hash.to_lowercase(), info);
+                }
+            }
+        }
+
+        Ok(RomDatabase { roms })
+    }
+
+    pub fn lookup(&self, rom: &[u8]) -> Option<&RomInfo> {
+        self.roms.get(&rom_hash(rom))
+    }
+
+    pub fn len(&self) -> usize {
+        self.roms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roms.is_empty()
+    }
+}
+
+fn parse_rom(title: &str, rom: &Value) -> Option<RomInfo> {
+    let (platform_id, platform) = rom["platforms"].as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .find_map(|id| platform_from_id(id).map(|platform| (id, platform)))?;
+
+    let mut quirks = platform.quirks();
+    for (name, value) in rom["quirkyPlatforms"][platform_id].as_object().into_iter().flatten() {
+        if let Some(value) = value.as_bool() { set_quirk(&mut quirks, name, value) }
+    }
+
+    let colors = rom["colors"]["pixels"].as_array().into_iter().flatten()
+        .filter_map(|color| color.as_str().and_then(|color| parse_color(color).ok()))
+        .collect();
+
+    let keys = rom["keys"].as_object().into_iter().flatten()
+        .filter_map(|(name, key)| key.as_u64().filter(|key| *key <= 0xf).map(|key| (name.clone(), key as u8)))
+        .collect();
+
+    Some(RomInfo {
+        title: title.to_string(),
+        platform,
+        quirks,
+        cdp1802: platform_id == "hybridVIP",
+        tickrate: rom["tickrate"].as_u64().filter(|tickrate| *tickrate > 0).map(|tickrate| tickrate as u32),
+        colors,
+        keys,
+    })
+}
+
+fn platform_from_id(id: &str) -> Option<Platform> {
+    match id {
+        "originalChip8" | "hybridVIP" | "modernChip8" => Some(Platform::CosmacVip),
+        "chip8x" => Some(Platform::Chip8X),
+        "chip48" => Some(Platform::Chip48),
+        "superchip1" => Some(Platform::SuperChip10),
+        "superchip" => Some(Platform::SuperChip11),
+        "megachip8" => Some(Platform::MegaChip),
+        "xochip" => Some(Platform::XoChip),
+        _ => None,
+    }
+}
+
+fn set_quirk(quirks: &mut Quirks, name: &str, value: bool) {
+    let builder = quirks.to_builder();
+    *quirks = match name {
+        "shift" => builder.shifting(value),
+        "memoryLeaveIUnchanged" => builder.memory(!value),
+        "memoryIncrementByX" => builder.memory_increment_by_x(value),
+        "wrap" => builder.clipping(!value),
+        "jump" => builder.jumping(value),
+        "vblank" => builder.display_wait(value),
+        "logic" => builder.vf_reset(value),
+        _ => return,
+    }.build();
+}
+
+pub fn rom_hash(rom: &[u8]) -> String {
+    sha1_smol::Sha1::from(rom).digest().to_string()
+}
@@ -0,0 +1,105 @@
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use zip::ZipArchive;
+
+use crate::download::{Downloader, is_url, url_file_name};
+
+// The 24 bit I register of MegaChip roms reaches 16M
+pub const MAX_ROM_SIZE: usize = 1 << 24;
+// Files picked from archives and directories, single files are loaded whatever they are called
+const ROM_EXTENSIONS: [&str; 12] = ["ch8", "c8", "c8h", "c8e", "c8x", "ch48", "sc8", "sc11", "xo8", "mc8", "hc8", "bin"];
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RomFile {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+// Every rom at a path or an http(s) url
+pub fn load_source(source: &str, downloader: &Downloader) -> Result<Vec<RomFile>, String> {
+    if !is_url(source) { return load_roms(Path::new(source)); }
+    let mut roms = unpack(&url_file_name(source), downloader.fetch(source)?)?;
+    roms.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(roms)
+}
+
+// Every rom in a file, a .zip or .gz archive or a directory and its subdirectories, sorted by name
+pub fn load_roms(path: &Path) -> Result<Vec<RomFile>, String> {
+    let mut roms = if path.is_dir() {
+        load_directory(path, path)?
+    } else {
+        let data = fs::read(path).map_err(|err| format!("Error encountered while loading file from path {} : {}", path.display(), err))?;
+        unpack(&file_name(path), data)?
+    };
+    roms.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(roms)
+}
+
+// Opens archives by their extension, anything else is a rom
+pub fn unpack(name: &str, data: Vec<u8>) -> Result<Vec<RomFile>, String> {
+    match extension(name).as_str() {
+        "zip" => unzip(name, data),
+        "gz" => {
+            let name = &name[..name.len() - ".gz".len()];
+            let mut rom = Vec::new();
+            GzDecoder::new(data.as_slice()).take(MAX_ROM_SIZE as u64 + 1).read_to_end(&mut rom)
+                .map_err(|err| format!("Error encountered while unpacking {} : {}", name, err))?;
+            if rom.len() > MAX_ROM_SIZE { return Err(format!("{} is bigger than {} bytes", name, MAX_ROM_SIZE)); }
+            Ok(vec![RomFile { name: name.to_string(), data: rom }])
+        }
+        _ if data.len() > MAX_ROM_SIZE => Err(format!("{} is bigger than {} bytes", name, MAX_ROM_SIZE)),
+        _ => Ok(vec![RomFile { name: name.to_string(), data }]),
+    }
+}
+
+fn unzip(name: &str, data: Vec<u8>) -> Result<Vec<RomFile>, String> {
+    let error = |err: zip::result::ZipError| format!("Error encountered while unpacking {} : {}", name, err);
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(error)?;
+
+    let mut roms = Vec::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(error)?;
+        if !file.is_file() || file.size() > MAX_ROM_SIZE as u64 || !is_loadable(file.name()) { continue }
+        // the size in the header can't be trusted
+        let mut data = Vec::new();
+        file.by_ref().take(MAX_ROM_SIZE as u64 + 1).read_to_end(&mut data)
+            .map_err(|err| format!("Error encountered while unpacking {} : {}", name, err))?;
+        if data.len() > MAX_ROM_SIZE { continue }
+        roms.extend(unpack(file.name(), data)?);
+    }
+    Ok(roms)
+}
+
+fn load_directory(root: &Path, directory: &Path) -> Result<Vec<RomFile>, String> {
+    let entries = fs::read_dir(directory).map_err(|err| format!("Error encountered while listing {} : {}", directory.display(), err))?;
+
+    let mut roms = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|err| err.to_string())?.path();
+        if path.is_dir() {
+            roms.extend(load_directory(root, &path)?);
+            continue;
+        }
+        let name = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+        if !is_loadable(&name) { continue }
+        let data = fs::read(&path).map_err(|err| format!("Error encountered while loading file from path {} : {}", path.display(), err))?;
+        roms.extend(unpack(&name, data)?);
+    }
+    Ok(roms)
+}
+
+fn is_loadable(name: &str) -> bool {
+    let extension = extension(name);
+    extension == "zip" || extension == "gz" || ROM_EXTENSIONS.contains(&extension.as_str())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or(path.to_string_lossy(), |name| name.to_string_lossy()).to_string()
+}
+
+fn extension(name: &str) -> String {
+    name.rsplit_once('.').map_or(String::new(), |(_, extension)| extension.to_lowercase())
+}
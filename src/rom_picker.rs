@@ -0,0 +1,56 @@
+use std::io::{BufRead, Write};
+
+use crate::rom_database::RomDatabase;
+use crate::rom_loader::RomFile;
+
+// Lists the roms of an archive or directory by title and lets the user choose one on the terminal
+pub struct RomPicker {
+    titles: Vec<String>,
+}
+
+impl RomPicker {
+    // Roms known to the database are listed by their title followed by the file name
+    pub fn new(roms: &[RomFile], database: &RomDatabase) -> Self {
+        let titles = roms.iter().map(|rom| match database.lookup(&rom.data) {
+            Some(info) => format!("{} ({})", info.title, rom.name),
+            None => rom.name.clone(),
+        }).collect();
+        RomPicker { titles }
+    }
+
+    pub fn titles(&self) -> &[String] {
+        &self.titles
+    }
+
+    // Indices of the roms whose title contains every word of the query, ignoring case
+    pub fn filter(&self, query: &str) -> Vec<usize> {
+        let words: Vec<_> = query.split_whitespace().map(str::to_lowercase).collect();
+        (0..self.titles.len()).filter(|index| {
+            let title = self.titles[*index].to_lowercase();
+            words.iter().all(|word| title.contains(word.as_str()))
+        }).collect()
+    }
+
+    // Shows the matching roms until a number is entered, other input filters the list. None when the input ends or on q
+    pub fn pick(&self, mut input: impl BufRead, mut output: impl Write) -> Result<Option<usize>, String> {
+        let mut shown = self.filter("");
+        loop {
+            for (number, index) in shown.iter().enumerate() {
+                writeln!(output, "{:>4} {}", number + 1, self.titles[*index]).map_err(|err| err.to_string())?;
+            }
+            if shown.is_empty() { writeln!(output, "No matching roms").map_err(|err| err.to_string())? }
+            write!(output, "Number to start, text to search, empty to list all, q to quit: ").map_err(|err| err.to_string())?;
+            output.flush().map_err(|err| err.to_string())?;
+
+            let mut line = String::new();
+            if input.read_line(&mut line).map_err(|err| err.to_string())? == 0 { return Ok(None); }
+            match line.trim() {
+                "q" => return Ok(None),
+                query => match query.parse::<usize>() {
+                    Ok(number @ 1..) if number <= shown.len() => return Ok(Some(shown[number - 1])),
+                    _ => shown = self.filter(query),
+                },
+            }
+        }
+    }
+}
@@ -1,58 +1,213 @@
-use sdl2::pixels::Color;
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
-use sdl2::render::Canvas;
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
 use sdl2::Sdl;
-use sdl2::video::Window;
+use sdl2::video::{FullscreenType, Window, WindowContext};
 
-use crate::chip8::{CH8_HEIGHT, CH8_WIDTH, Chip8};
+use crate::chip8::Chip8;
+use crate::framebuffer::Resolution;
+use crate::osd::{Corner, GLYPH_HEIGHT, Osd, text_pixels, text_width};
+use crate::palette::{Palette, Theme, THEMES};
+use crate::persistence::{fade, Persistence, PersistenceFilter};
+use crate::quirks::Platform;
+use crate::render::{letterbox, MAX_SCALE, WindowSettings};
+use crate::rng::Rng;
 
-const WHITE: Color = Color::RGB(255, 255, 255);
-const BLACK: Color = Color::RGB(0, 0, 0);
+const GRID: Color = Color::RGBA(0, 0, 0, 0x60);
+const MIN_GRID_PIXEL: u32 = 4;
+const OSD_BACKGROUND: Color = Color::RGBA(0, 0, 0, 0xa0);
+const OSD_TEXT: Color = Color::RGB(0xff, 0xff, 0xff);
+// Window height per font pixel
+const OSD_FONT_DIVISOR: u32 = 160;
 
-pub struct SDLDriver {
-    pub sdl_context: Sdl,
+pub struct SDLDriver<'a> {
     canvas: Canvas<Window>,
-    pub width: u32,
-    pub height: u32,
-    pub scale: u32,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    // streaming texture of the current resolution, created again when it changes
+    texture: Option<Texture<'a>>,
+    palette: Palette,
+    theme: Option<Theme>,
+    persistence: PersistenceFilter,
+    settings: WindowSettings,
+    stale: bool,
+    last_window: (u32, u32),
+    last_lines: Vec<(Corner, String)>,
 }
 
-impl SDLDriver {
-    pub fn new(scale: u32) -> Result<SDLDriver, String> {
-        let sdl_context = sdl2::init()?;
-        let video = sdl_context.video()?;
+// The canvas of the driver, its texture creator is owned by the caller so the driver can borrow it
+pub fn open_window(sdl_context: &Sdl, settings: WindowSettings, palette: Palette) -> Result<Canvas<Window>, String> {
+    let video = sdl_context.video()?;
 
-        let width = CH8_WIDTH as u32 * scale;
-        let height = CH8_HEIGHT as u32 * scale;
+    let (width, height) = window_size(settings.scale);
+    let mut window = video
+        .window("yac8", width, height)
+        .position_centered()
+        .resizable()
+        .opengl()
+        .build()
+        .map_err(|e| e.to_string())?;
+    if settings.fullscreen { window.set_fullscreen(FullscreenType::Desktop)? }
 
-        let window = video
-            .window("yac8", width, height)
-            .position_centered()
-            .opengl()
-            .build()
-            .map_err(|e| e.to_string())?;
+    let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+    canvas.set_blend_mode(BlendMode::Blend);
 
-        let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
-
-        canvas.set_draw_color(BLACK);
-        canvas.clear();
-        canvas.present();
+    let [r, g, b, _] = palette.background();
+    canvas.set_draw_color(Color::RGB(r, g, b));
+    canvas.clear();
+    canvas.present();
+    Ok(canvas)
+}
 
-        Ok(SDLDriver { sdl_context, canvas, width, height, scale })
+impl<'a> SDLDriver<'a> {
+    pub fn new(canvas: Canvas<Window>, texture_creator: &'a TextureCreator<WindowContext>, settings: WindowSettings, palette: Palette, persistence: Persistence) -> Self {
+        let theme = THEMES.iter().copied().find(|theme| theme.palette() == palette);
+        SDLDriver {
+            canvas,
+            texture_creator,
+            texture: None,
+            palette,
+            theme,
+            persistence: PersistenceFilter::new(persistence),
+            settings,
+            stale: true,
+            last_window: (0, 0),
+            last_lines: Vec::new(),
+        }
     }
 
-    pub fn draw(&mut self, chip8: &Chip8) {
-        for (iy, y) in chip8.vram.iter().enumerate() {
-            for (ix, x) in y.iter().enumerate() {
-                if *x { self.canvas.set_draw_color(WHITE) } else { self.canvas.set_draw_color(BLACK); }
-                let rect = Rect::new((ix as u32 * self.scale) as i32, (iy as u32 * self.scale) as i32, self.scale, self.scale);
-                //TODO if it's the same we don't need to redraw
-                self.canvas.fill_rect(rect).unwrap();
+    // Frames that look like the last drawn one are skipped
+    pub fn draw<R: Rng>(&mut self, chip8: &Chip8<R>, osd: &Osd) -> Result<(), String> {
+        let lines = osd.lines(chip8);
+        let faded = self.persistence.update(&chip8.vram);
+        let window = self.canvas.output_size()?;
+        if !(faded || chip8.vram_changed || self.stale) && (window, &lines) == (self.last_window, &self.last_lines) { return Ok(()); }
+
+        let size = (chip8.vram.width() as u32, chip8.vram.height() as u32);
+        let texture = match self.texture.take() {
+            Some(texture) if (texture.query().width, texture.query().height) == size => texture,
+            _ => self.texture_creator.create_texture_streaming(PixelFormatEnum::ARGB8888, size.0, size.1).map_err(|e| e.to_string())?,
+        };
+        let texture = self.texture.insert(texture);
+        let (persistence, palette) = (&self.persistence, &self.palette);
+        texture.with_lock(None, |buffer, pitch| {
+            for (x, y, value, intensity) in persistence.pixels() {
+                // the MegaChip screen brings its own colours
+                let color = |value: u8| match (chip8.vram.color(x, y), chip8.config().platform()) {
+                    (Some([r, g, b, _]), _) => [r, g, b],
+                    (None, Platform::Chip8X) => chip8.color_board().color(x, y, value != 0),
+                    (None, _) => {
+                        let [r, g, b, _] = palette.color(value);
+                        [r, g, b]
+                    }
+                };
+                let [r, g, b] = fade(color(0), color(value), intensity);
+                let offset = y * pitch + x * 4;
+                buffer[offset..offset + 4].copy_from_slice(&u32::from_be_bytes([0xff, r, g, b]).to_ne_bytes());
             }
-        }
+        })?;
 
+        let (x, y, width, height) = letterbox(window, size);
+        self.canvas.set_draw_color(Color::RGB(0, 0, 0));
+        self.canvas.clear();
+        self.canvas.copy(texture, None, Rect::new(x, y, width, height))?;
+        if self.settings.grid { self.draw_grid((x, y), size, width / size.0)? }
+        self.draw_osd(window, &lines)?;
         self.canvas.present();
+
+        (self.stale, self.last_window, self.last_lines) = (false, window, lines);
+        Ok(())
+    }
+
+    // Text on dark boxes stacked from the corners, the font grows with the window
+    fn draw_osd(&mut self, (window_width, window_height): (u32, u32), lines: &[(Corner, String)]) -> Result<(), String> {
+        let font = (window_height / OSD_FONT_DIVISOR).max(1) as i32;
+        let line_height = (GLYPH_HEIGHT as i32 + 2) * font;
+        let mut stacked = [0; 3];
+
+        for (corner, text) in lines {
+            let width = (text_width(text) as i32 + 2) * font;
+            let index = *corner as usize;
+            let x = match corner {
+                Corner::TopRight => window_width as i32 - width - font,
+                _ => font,
+            };
+            let y = match corner {
+                Corner::BottomLeft => window_height as i32 - (stacked[index] + 1) * (line_height + font),
+                _ => font + stacked[index] * (line_height + font),
+            };
+            stacked[index] += 1;
+
+            self.canvas.set_draw_color(OSD_BACKGROUND);
+            self.canvas.fill_rect(Rect::new(x, y, width as u32, line_height as u32))?;
+            self.canvas.set_draw_color(OSD_TEXT);
+            let rects: Vec<_> = text_pixels(text)
+                .map(|(px, py)| Rect::new(x + (px as i32 + 1) * font, y + (py as i32 + 1) * font, font as u32, font as u32))
+                .collect();
+            self.canvas.fill_rects(&rects)?;
+        }
+        Ok(())
+    }
+
+    // Lines along the pixel borders, left out when the pixels are too small to see them
+    fn draw_grid(&mut self, (x, y): (i32, i32), (columns, rows): (u32, u32), pixel: u32) -> Result<(), String> {
+        if pixel < MIN_GRID_PIXEL { return Ok(()); }
+        let (width, height) = ((columns * pixel) as i32, (rows * pixel) as i32);
+
+        self.canvas.set_draw_color(GRID);
+        for column in 1..columns as i32 {
+            let line_x = x + column * pixel as i32;
+            self.canvas.draw_line((line_x, y), (line_x, y + height - 1))?;
+        }
+        for row in 1..rows as i32 {
+            let line_y = y + row * pixel as i32;
+            self.canvas.draw_line((x, line_y), (x + width - 1, line_y))?;
+        }
+        Ok(())
+    }
+
+    // Forces the next frame to be drawn even when it didn't change
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    pub fn toggle_fullscreen(&mut self) -> Result<(), String> {
+        let window = self.canvas.window_mut();
+        let fullscreen = match window.fullscreen_state() {
+            FullscreenType::Off => FullscreenType::Desktop,
+            _ => FullscreenType::Off,
+        };
+        window.set_fullscreen(fullscreen)?;
+        self.settings.fullscreen = fullscreen != FullscreenType::Off;
+        self.invalidate();
+        Ok(())
     }
-}
 
+    pub fn toggle_grid(&mut self) -> bool {
+        self.settings.grid = !self.settings.grid;
+        self.invalidate();
+        self.settings.grid
+    }
+
+    // Resizes the window to the new scale, the image follows through the resize event
+    pub fn change_scale(&mut self, delta: i32) -> Result<u32, String> {
+        if self.settings.fullscreen { return Ok(self.settings.scale); }
+        self.settings.scale = (self.settings.scale as i32 + delta).clamp(1, MAX_SCALE as i32) as u32;
+        let (width, height) = window_size(self.settings.scale);
+        self.canvas.window_mut().set_size(width, height).map_err(|e| e.to_string())?;
+        self.invalidate();
+        Ok(self.settings.scale)
+    }
 
+    // Switches to the next builtin theme, starting with the first one after a custom palette
+    pub fn next_theme(&mut self) -> Theme {
+        let theme = self.theme.map_or(Theme::default(), |theme| theme.next());
+        self.theme = Some(theme);
+        self.palette = theme.palette();
+        self.invalidate();
+        theme
+    }
+}
+
+fn window_size(scale: u32) -> (u32, u32) {
+    (Resolution::Lores.width() as u32 * scale, Resolution::Lores.height() as u32 * scale)
+}
@@ -0,0 +1,40 @@
+// serde only knows arrays of up to 32 elements, memory and screens go through here with #[serde(with = "...")],
+// boxed or not
+use core::borrow::Borrow;
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub fn serialize<S: Serializer, T: Serialize, A: Borrow<[T; N]>, const N: usize>(array: &A, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(array.borrow())
+}
+
+pub fn deserialize<'de, D, T, A, const N: usize>(deserializer: D) -> Result<A, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default + Copy,
+    A: From<[T; N]>,
+{
+    deserializer.deserialize_seq(ArrayVisitor(PhantomData)).map(A::from)
+}
+
+struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de> + Default + Copy, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+    type Value = [T; N];
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "an array of {} elements", N)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut array = [T::default(); N];
+        for (i, element) in array.iter_mut().enumerate() {
+            *element = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(i, &self))?;
+        }
+        if seq.next_element::<T>()?.is_some() { return Err(A::Error::invalid_length(N + 1, &self)); }
+        Ok(array)
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::quirks::Quirks;
+
+// Options of the config file, named like the command line flags they stand for
+#[derive(Clone, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub database: Option<String>,
+    pub cache_dir: Option<String>,
+    pub platform: Option<String>,
+    pub tickrate: Option<u32>,
+    pub quirks: Option<QuirkSetting>,
+    pub timing: Option<String>,
+    pub cdp1802: Option<bool>,
+    pub theme: Option<String>,
+    pub palette: Option<String>,
+    pub foreground: Option<String>,
+    pub background: Option<String>,
+    pub persistence: Option<String>,
+    pub scale: Option<u32>,
+    pub fullscreen: Option<bool>,
+    pub grid: Option<bool>,
+    pub hotkeys: Option<String>,
+    pub keymap: Option<String>,
+    pub keys: Option<String>,
+    pub controller: Option<String>,
+    pub controller2: Option<String>,
+    pub fast_forward: Option<u32>,
+    // Settings of single roms by their sha1, applied on top of the others
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub roms: BTreeMap<String, Settings>,
+}
+
+// A compact list like "schip,-shifting" or every quirk spelled out, missing ones are off
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum QuirkSetting {
+    List(String),
+    Quirks(Quirks),
+}
+
+impl Settings {
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let settings: Settings = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        // these are used before the rom is known
+        for rom in settings.roms.values() {
+            if !rom.roms.is_empty() { return Err("roms can only be set at the top level".to_string()); }
+            if rom.database.is_some() || rom.cache_dir.is_some() { return Err("database and cache_dir can't be set per rom".to_string()); }
+        }
+        Ok(settings)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path).map_err(|err| format!("Error encountered while loading config from path {} : {}", path.display(), err))?;
+        Settings::parse(&json).map_err(|err| format!("{} in config {}", err, path.display()))
+    }
+
+    // The file given with --config, otherwise the default one when it exists
+    pub fn find(args: &[String]) -> Result<Self, String> {
+        match args.iter().position(|arg| arg == "--config") {
+            Some(i) => Settings::load(Path::new(args.get(i + 1).ok_or("--config requires a path")?)),
+            None => match default_config_path().filter(|path| path.is_file()) {
+                Some(path) => Settings::load(&path),
+                None => Ok(Settings::default()),
+            },
+        }
+    }
+
+    // Flags of the global settings followed by the ones of the rom with this sha1
+    pub fn args_for(&self, rom_hash: &str) -> Vec<String> {
+        let rom = self.roms.iter().find(|(hash, _)| hash.eq_ignore_ascii_case(rom_hash)).map(|(_, rom)| rom.to_args());
+        self.to_args().into_iter().chain(rom.into_iter().flatten()).collect()
+    }
+
+    // Command line flags with the same effect, put in front of the real ones so those win
+    pub fn to_args(&self) -> Vec<String> {
+        let quirks = self.quirks.as_ref().map(|quirks| match quirks {
+            QuirkSetting::List(list) => list.clone(),
+            QuirkSetting::Quirks(quirks) => quirks.to_string(),
+        });
+        let values = [
+            ("--database", self.database.clone()),
+            ("--cache-dir", self.cache_dir.clone()),
+            ("--platform", self.platform.clone()),
+            ("--tickrate", self.tickrate.map(|tickrate| tickrate.to_string())),
+            ("--quirks", quirks),
+            ("--timing", self.timing.clone()),
+            ("--theme", self.theme.clone()),
+            ("--palette", self.palette.clone()),
+            ("--foreground", self.foreground.clone()),
+            ("--background", self.background.clone()),
+            ("--persistence", self.persistence.clone()),
+            ("--scale", self.scale.map(|scale| scale.to_string())),
+            ("--hotkeys", self.hotkeys.clone()),
+            ("--keymap", self.keymap.clone()),
+            ("--keys", self.keys.clone()),
+            ("--controller", self.controller.clone()),
+            ("--controller2", self.controller2.clone()),
+            ("--fast-forward", self.fast_forward.map(|speed| speed.to_string())),
+        ];
+        // switches set to false become --no-<flag> so they can turn off what an earlier source turned on
+        let switches = [("--cdp1802", self.cdp1802), ("--fullscreen", self.fullscreen), ("--grid", self.grid)];
+
+        let mut args = Vec::new();
+        for (flag, value) in values {
+            if let Some(value) = value { args.extend([flag.to_string(), value]) }
+        }
+        for (flag, on) in switches {
+            match on {
+                Some(true) => args.push(flag.to_string()),
+                Some(false) => args.push(flag.replacen("--", "--no-", 1)),
+                None => {}
+            }
+        }
+        args
+    }
+}
+
+// $XDG_DATA_HOME/yac8/states/<sha1>.json or ~/.local/share/yac8/states/<sha1>.json, one save state per rom
+pub fn state_path(rom_hash: &str) -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+        .map(|dir| dir.join("yac8").join("states").join(format!("{}.json", rom_hash)))
+}
+
+// $XDG_CONFIG_HOME/yac8/config.json or ~/.config/yac8/config.json
+pub fn default_config_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("yac8").join("config.json"))
+}
@@ -0,0 +1,57 @@
+use crate::registers::Registers;
+
+// 1.76 MHz clock with 8 clock cycles per 1802 machine cycle, at 60 frames per second
+pub const VIP_CYCLES_PER_FRAME: u32 = 3668;
+// The display interrupt routine runs during the 128 visible scanlines while the 1861 steals cycles for DMA
+pub const VIP_DISPLAY_CYCLES: u32 = 1832;
+// Fetching and decoding in the interpreter loop, paid by every instruction
+const VIP_FETCH_CYCLES: u32 = 40;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimingMode {
+    // Every instruction costs one of Config::cycles_per_frame
+    #[default]
+    Instructions,
+    // Every instruction costs its machine cycles on the VIP interpreter out of the frame budget
+    CosmacVip,
+}
+
+// Machine cycles of an instruction on the VIP interpreter, waiting for the display interrupt is not included
+pub(crate) fn vip_cycles(opcode: u16, registers: &Registers) -> u32 {
+    let x = ((opcode >> 8) & 0xf) as u8;
+    let n = (opcode & 0xf) as u32;
+
+    let execute = match opcode >> 12 {
+        0x0 => match opcode {
+            0x00e0 => 3102,
+            0x00ee => 22,
+            _ => 26,
+        },
+        0x1 => 22,
+        0x2 => 26,
+        0x3 | 0x4 => 10,
+        0x5 | 0x9 => 14,
+        0x6 => 6,
+        0x7 => 10,
+        0x8 => 44,
+        0xa => 12,
+        0xb => 22,
+        0xc => 36,
+        0xd => 68 + n * (46 + 20 * (registers.get(x) & 7) as u32),
+        0xe => 14,
+        _ => match opcode & 0xff {
+            0x0a => 19,
+            0x1e => 16,
+            0x29 => 20,
+            0x33 => {
+                let vx = registers.get(x) as u32;
+                80 + 12 * (vx / 100 + vx / 10 % 10 + vx % 10)
+            }
+            0x55 | 0x65 => 14 + 14 * (x as u32 + 1),
+            _ => 10,
+        },
+    };
+
+    VIP_FETCH_CYCLES + execute
+}
@@ -0,0 +1,48 @@
+use yac8::chip8::{Chip8, Chip8Error, Config};
+use yac8::chip8_runner::{HeadlessRunner, StopReason};
+
+// calls the 1802 routine at 0x206, then jumps to itself
+const HYBRID: [u8; 4] = [0x02, 0x06, 0x12, 0x02];
+// V0 = 0x42 through 0xEF0 and the top left 8 pixels on through the display page in RB, then SEP R4
+const SET_V0_AND_DRAW: [u8; 18] = [
+    0xF8, 0x0E, 0xB8, 0xF8, 0xF0, 0xA8, 0xF8, 0x42, 0x58,
+    0x9B, 0xB9, 0xF8, 0x00, 0xA9, 0xF8, 0xFF, 0x59,
+    0xD4,
+];
+// BR to itself
+const NEVER_RETURNS: [u8; 2] = [0x30, 0x06];
+
+fn hybrid_rom(routine: &[u8]) -> Vec<u8> {
+    let mut rom = HYBRID.to_vec();
+    rom.extend([0x00, 0x00]);
+    rom.extend(routine);
+    rom
+}
+
+fn run(rom: &[u8], config: Config) -> (Chip8, StopReason) {
+    let mut chip8 = Chip8::new(rom, config).unwrap();
+    let summary = HeadlessRunner::new().stop_on_self_jump().max_frames(10).run(&mut chip8);
+    (chip8, summary.reason)
+}
+
+#[test]
+fn machine_code_needs_cdp1802() {
+    let (_, reason) = run(&hybrid_rom(&NEVER_RETURNS), Config::ch8());
+    assert_eq!(reason, StopReason::Error(Chip8Error::MachineCodeRoutine(0x0206)));
+}
+
+#[test]
+fn routine_sees_interpreter_state() {
+    let (chip8, reason) = run(&hybrid_rom(&SET_V0_AND_DRAW), Config::ch8().with_cdp1802(true));
+
+    assert_eq!(reason, StopReason::SelfJump(0x202));
+    assert_eq!(chip8.get_register(0), 0x42);
+    assert!((0..8).all(|x| chip8.vram.get(x, 0)));
+    assert!(!chip8.vram.get(8, 0));
+}
+
+#[test]
+fn routine_that_never_returns() {
+    let (_, reason) = run(&hybrid_rom(&NEVER_RETURNS), Config::ch8().with_cdp1802(true));
+    assert_eq!(reason, StopReason::Error(Chip8Error::MachineCodeTimeout(0x0206)));
+}
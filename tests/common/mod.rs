@@ -1,2 +1,2 @@
-pub mod vram_dumps;
+pub mod snapshots;
 pub mod test_utils;
\ No newline at end of file
@@ -0,0 +1,38 @@
+use std::env;
+use std::path::PathBuf;
+
+use yac8::framebuffer::Framebuffer;
+
+const UPDATE_SNAPSHOTS: &str = "UPDATE_SNAPSHOTS";
+
+pub fn assert_snapshot(name: &str, vram: &Framebuffer) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "snapshots", &format!("{}.txt", name)].iter().collect();
+    let actual = vram.to_string();
+
+    if env::var_os(UPDATE_SNAPSHOTS).is_some() {
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("Missing snapshot {} ({}), run with {}=1 to create it", path.display(), err, UPDATE_SNAPSHOTS));
+    if expected.replace("\r\n", "\n") != actual {
+        panic!("Snapshot {} does not match, run with {}=1 to update it\n{}", name, UPDATE_SNAPSHOTS, side_by_side(&expected, &actual));
+    }
+}
+
+fn side_by_side(expected: &str, actual: &str) -> String {
+    let mut diff = format!("{:<64}     {}\n", "expected", "actual");
+    let mut expected_rows = expected.lines();
+    let mut actual_rows = actual.lines();
+    loop {
+        match (expected_rows.next(), actual_rows.next()) {
+            (None, None) => return diff,
+            (e, a) => {
+                let (e, a) = (e.unwrap_or(""), a.unwrap_or(""));
+                let marker = if e == a { "   " } else { " ! " };
+                diff += &format!("{:<64} {} {}\n", e, marker, a);
+            }
+        }
+    }
+}
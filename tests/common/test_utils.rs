@@ -1,27 +1,45 @@
 use std::path::PathBuf;
 
-use yac8::chip8::{Chip8, Chip8Vram, Config};
+use yac8::chip8::{Chip8, Config};
 use yac8::chip8_runner::run_stop_on_blocked;
 use yac8::cli::load_rom_from_path;
+use yac8::framebuffer::Framebuffer;
+use yac8::movie::Movie;
 use yac8::quirks::{SCHIP_QUIRKS, XOCHIP_QUIRKS};
+use yac8::timing::TimingMode;
 
-pub fn dump_vram_when_blocked(data: &[u8], memory_start: u16) -> Chip8Vram {
+pub fn dump_vram_when_blocked(data: &[u8], memory_start: u16) -> Framebuffer {
     let mut chip8 = ch8_with_test_rom();
     chip8.load_to_memory(data, memory_start);
     run_stop_on_blocked(&mut chip8);
     chip8.vram
 }
 
-fn chip_with_test_rom(config: Config) -> Chip8 {
+fn load_test_rom() -> Vec<u8> {
     let rom_path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "roms", "chip8-test-suite.ch8"].iter().collect();
-    let rom = load_rom_from_path(rom_path.to_str().unwrap());
-    Chip8::new(rom, config)
+    load_rom_from_path(rom_path.to_str().unwrap())
+}
+
+fn chip_with_test_rom(config: Config) -> Chip8 {
+    Chip8::new(&load_test_rom(), config).unwrap()
+}
+
+pub fn movie_with_test_rom(name: &str) -> (Chip8, Movie) {
+    let movie_path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "movies", name].iter().collect();
+    let movie = Movie::load(&movie_path).unwrap();
+    let rom = load_test_rom();
+    movie.check_rom(&rom).unwrap();
+    (Chip8::new(&rom, movie.config()).unwrap(), movie)
 }
 
 pub fn ch8_with_test_rom() -> Chip8 {
     chip_with_test_rom(Config::ch8())
 }
 
+pub fn ch8_with_test_rom_and_timing(timing: TimingMode) -> Chip8 {
+    chip_with_test_rom(Config::ch8().with_timing(timing))
+}
+
 pub fn schip_with_test_rom() -> Chip8 {
     chip_with_test_rom(Config::new(false, SCHIP_QUIRKS))
 }
@@ -0,0 +1,76 @@
+use yac8::chip8::{Chip8, Config};
+use yac8::chip8_runner::run_frame;
+use yac8::cli::parse_args;
+use yac8::controller::{ControllerMap, is_control};
+use yac8::keys::Keys;
+
+// V2 = 1 while 5 and C are both held, then loops
+const BOTH_HELD: [u8; 16] = [0x60, 0x05, 0x61, 0x0C, 0xE0, 0x9E, 0x12, 0x0E, 0xE1, 0x9E, 0x12, 0x0E, 0x62, 0x01, 0x12, 0x0E];
+
+fn args(list: &[&str]) -> impl Iterator<Item=String> {
+    list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().into_iter()
+}
+
+#[test]
+fn default_bindings() {
+    let map = ControllerMap::default();
+    assert_eq!(map.key("dpup"), Some(0x5));
+    assert_eq!(map.key("leftx+"), Some(0x9));
+    assert_eq!(map.key("a"), Some(0x6));
+    assert!(ControllerMap::new().bindings().is_empty());
+
+    let second = ControllerMap::player(1);
+    assert_eq!(second.key("dpup"), Some(0xc));
+    assert_eq!(second.key("lefty+"), Some(0xd));
+    assert_eq!(second.key("a"), Some(0xb));
+
+    assert!(is_control("righttrigger+"));
+    assert!(is_control("start"));
+    assert!(!is_control("dpup+"));
+    assert!(!is_control("rightz-"));
+}
+
+#[test]
+fn parse_bindings() {
+    let map: ControllerMap = "5=DPUP, 5=lefty-, f=start".parse().unwrap();
+    assert_eq!(map.key("dpup"), Some(0x5));
+    assert_eq!(map.key("lefty-"), Some(0x5));
+    assert_eq!(map.key("start"), Some(0xf));
+
+    assert!("5=turbo".parse::<ControllerMap>().is_err());
+    assert_eq!("1c=b".parse::<ControllerMap>().unwrap().key("b"), Some(0x1c));
+    assert!("20=a".parse::<ControllerMap>().is_err());
+}
+
+#[test]
+fn rom_actions_per_player() {
+    let options = parse_args(args(&["rom.ch8", "--controller2", "1=start"])).unwrap();
+    let keys = [("up", 0x1), ("down", 0x4), ("player2Up", 0xc), ("player2A", 0xd)].map(|(action, key)| (action.to_string(), key));
+    let [first, second] = options.controller_maps(&keys).unwrap();
+
+    assert_eq!(first.key("dpup"), Some(0x1));
+    assert_eq!(first.key("lefty+"), Some(0x4));
+    assert_eq!(first.key("dpleft"), Some(0x7));
+    assert_eq!(second.key("dpup"), Some(0xc));
+    assert_eq!(second.key("a"), Some(0xd));
+    assert_eq!(second.key("start"), Some(0x1));
+    assert_eq!(second.key("dpleft"), Some(0xe));
+
+    assert!(parse_args(args(&["rom.ch8", "--controller", "5=wheel"])).is_err());
+    assert!(options.controller_maps(&[("up".to_string(), 0x20)]).is_err());
+    assert!(ControllerMap::new().bind("a", 0x20).is_err());
+}
+
+#[test]
+fn two_players_hold_keys_together() {
+    let [first, second] = [ControllerMap::player(0), ControllerMap::player(1)];
+    let held = |maps: &[&ControllerMap]| -> Keys { maps.iter().filter_map(|map| map.key("dpup")).collect() };
+
+    let mut chip8 = Chip8::new(&BOTH_HELD, Config::ch8()).unwrap();
+    run_frame(&mut chip8, held(&[&first])).unwrap();
+    assert_eq!(chip8.get_register(2), 0);
+
+    let mut chip8 = Chip8::new(&BOTH_HELD, Config::ch8()).unwrap();
+    run_frame(&mut chip8, held(&[&first, &second])).unwrap();
+    assert_eq!(chip8.get_register(2), 1);
+}
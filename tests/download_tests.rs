@@ -0,0 +1,100 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use yac8::cli::parse_args;
+use yac8::download::{Downloader, is_url, url_file_name};
+use yac8::rom_database::rom_hash;
+use yac8::rom_loader::load_source;
+
+// Answers every request with the status and body and returns the base url
+fn serve(status: &'static str, body: Vec<u8>) -> String {
+    serve_requests(status, body, usize::MAX)
+}
+
+// Stops listening after the given number of requests
+fn serve_requests(status: &'static str, body: Vec<u8>, requests: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming().take(requests) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let _ = write!(stream, "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, body.len());
+            let _ = stream.write_all(&body);
+        }
+    });
+    url
+}
+
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("yac8-cache-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn url_names() {
+    assert!(is_url("https://example.com/pong.ch8"));
+    assert!(!is_url("roms/pong.ch8"));
+    assert_eq!(url_file_name("http://127.0.0.1:8080/packs/games.zip?version=2"), "games.zip");
+    assert_eq!(url_file_name("http://127.0.0.1:8080/"), "download");
+    assert_eq!(url_file_name("http://127.0.0.1:8080"), "download");
+}
+
+#[test]
+fn download_and_cache() {
+    let rom = vec![0x00, 0xe0, 0x12, 0x00];
+    let url = serve_requests("200 OK", rom.clone(), 1) + "/pong.ch8";
+    let dir = cache_dir("download");
+    let downloader = Downloader::new().with_cache_dir(Some(dir.clone()));
+
+    let roms = load_source(&url, &downloader).unwrap();
+    assert_eq!((roms[0].name.as_str(), &roms[0].data), ("pong.ch8", &rom));
+    assert_eq!(fs::read(dir.join(rom_hash(&rom))).unwrap(), rom);
+    // the server is gone, the url was downloaded before
+    assert_eq!(downloader.fetch(&url).unwrap(), rom);
+    assert!(Downloader::new().with_cache_dir(None).fetch(&url).is_err());
+
+    // with the sha1 the cached file is used, the server would answer differently
+    let offline = downloader.with_sha1(Some(rom_hash(&rom).to_uppercase()));
+    assert_eq!(offline.fetch("http://127.0.0.1:9/missing.ch8").unwrap(), rom);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn rejects_bad_downloads() {
+    let downloader = Downloader::new().with_cache_dir(None);
+    let url = serve("200 OK", vec![0x12; 64]);
+    assert!(downloader.clone().with_max_size(16).fetch(&url).unwrap_err().contains("bigger than 16 bytes"));
+    assert!(downloader.clone().with_sha1(Some("0".repeat(40))).fetch(&url).unwrap_err().contains("has sha1"));
+    assert_eq!(downloader.fetch(&url).unwrap().len(), 64);
+
+    assert!(downloader.fetch(&serve("404 Not Found", Vec::new())).is_err());
+}
+
+#[test]
+fn times_out() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/slow.ch8", listener.local_addr().unwrap());
+    let downloader = Downloader::new().with_cache_dir(None).with_timeout(Duration::from_millis(200));
+    assert!(downloader.fetch(&url).is_err());
+    drop(listener);
+}
+
+#[test]
+fn cli_options() {
+    let args = ["https://example.com/pong.ch8", "--sha1", &"A".repeat(40), "--cache-dir", "cache"].map(String::from);
+    let options = parse_args(args.into_iter()).unwrap();
+    assert_eq!(options.sha1, Some("a".repeat(40)));
+    assert_eq!(options.cache_dir.as_deref(), Some("cache"));
+
+    assert!(parse_args(["rom.ch8", "--sha1", "abc"].map(String::from).into_iter()).is_err());
+}
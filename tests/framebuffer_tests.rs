@@ -0,0 +1,79 @@
+use yac8::framebuffer::{Framebuffer, MONOCHROME, Resolution};
+
+#[test]
+fn resolution_switch_clears() {
+    let mut framebuffer = Framebuffer::new(Resolution::Lores);
+    framebuffer.set(63, 31, true);
+    assert_eq!(framebuffer.iter().count(), 64 * 32);
+
+    framebuffer.set_resolution(Resolution::Hires);
+    assert_eq!((framebuffer.width(), framebuffer.height()), (128, 64));
+    assert_eq!(framebuffer.rows().count(), 64);
+    assert!(framebuffer.iter().all(|(_, _, value)| value == 0));
+    framebuffer.set(127, 63, true);
+    assert!(framebuffer.get(127, 63));
+
+    framebuffer.set_resolution(Resolution::Mega);
+    assert_eq!((framebuffer.width(), framebuffer.height()), (256, 192));
+    framebuffer.set(255, 191, true);
+    assert_eq!(framebuffer.iter().filter(|(_, _, value)| *value != 0).count(), 1);
+}
+
+#[test]
+fn toggle_reports_collisions_per_plane() {
+    let mut framebuffer = Framebuffer::new(Resolution::Lores);
+    framebuffer.set_planes(2);
+    assert!(!framebuffer.toggle(1, 1, 0b01));
+    assert!(!framebuffer.toggle(1, 1, 0b10));
+    assert_eq!(framebuffer.pixel(1, 1), 0b11);
+    assert!(framebuffer.toggle(1, 1, 0b01));
+    assert_eq!(framebuffer.pixel(1, 1), 0b10);
+
+    framebuffer.set_planes(1);
+    assert_eq!(framebuffer.pixel(1, 1), 0);
+}
+
+#[test]
+fn rgba_export() {
+    let mut framebuffer = Framebuffer::new(Resolution::Lores);
+    framebuffer.set(1, 0, true);
+    let rgba: Vec<_> = framebuffer.as_rgba(&MONOCHROME).collect();
+    assert_eq!(rgba.len(), 64 * 32);
+    assert_eq!(&rgba[..2], &MONOCHROME);
+}
+
+#[test]
+fn display_as_ascii() {
+    let mut framebuffer = Framebuffer::new(Resolution::Lores);
+    framebuffer.set_planes(2);
+    framebuffer.set_pixel(0, 0, 1);
+    framebuffer.set_pixel(1, 0, 2);
+    let ascii = framebuffer.to_string();
+    assert!(ascii.starts_with("#2...."));
+    assert_eq!(ascii.lines().count(), 32);
+}
+
+#[test]
+fn scrolling_drops_pixels_at_the_edges() {
+    let mut framebuffer = Framebuffer::new(Resolution::Lores);
+    framebuffer.set(0, 0, true);
+    framebuffer.set(63, 31, true);
+    framebuffer.scroll(4, 2);
+    assert!(framebuffer.get(4, 2));
+    assert_eq!(framebuffer.iter().filter(|(_, _, value)| *value != 0).count(), 1);
+
+    framebuffer.scroll(-5, 0);
+    assert!(framebuffer.iter().all(|(_, _, value)| value == 0));
+}
+
+#[test]
+fn planes_clear_and_scroll_on_their_own() {
+    let mut framebuffer = Framebuffer::new(Resolution::Lores);
+    framebuffer.set_planes(2);
+    framebuffer.set_pixel(0, 0, 0b11);
+    framebuffer.scroll_planes(1, 0, 0b10);
+    assert_eq!((framebuffer.pixel(0, 0), framebuffer.pixel(1, 0)), (0b01, 0b10));
+
+    framebuffer.clear_planes(0b01);
+    assert_eq!((framebuffer.pixel(0, 0), framebuffer.pixel(1, 0)), (0, 0b10));
+}
@@ -0,0 +1,71 @@
+use yac8::chip8::Config;
+use yac8::hotkeys::{Command, Hotkeys};
+use yac8::keymap::{Keymap, PRESETS};
+use yac8::keys::Keys;
+use yac8::movie::{Movie, Session};
+use yac8::osd::Menu;
+
+#[test]
+fn default_bindings() {
+    let hotkeys = Hotkeys::default();
+    assert_eq!(hotkeys.command("F6"), Some(Command::Pause));
+    assert_eq!(hotkeys.command("pause"), Some(Command::Pause));
+    assert_eq!(hotkeys.command("escape"), Some(Command::Quit));
+    assert_eq!(hotkeys.command("="), Some(Command::ScaleUp));
+    assert_eq!(hotkeys.command("Keypad +"), None);
+    assert_eq!(hotkeys.command("Q"), None);
+}
+
+#[test]
+fn presets_leave_the_hotkeys_alone() {
+    let name = |key: &str| Some(key.to_lowercase());
+    for preset in PRESETS {
+        assert_eq!(Hotkeys::default().conflicts(&Keymap::preset(preset).unwrap(), name), vec![], "{}", preset);
+    }
+
+    let mut hotkeys = Hotkeys::default();
+    hotkeys.apply("pause=w").unwrap();
+    assert_eq!(hotkeys.conflicts(&Keymap::default(), name), vec![("w".to_string(), Command::Pause, 0x5)]);
+
+    // names of the same key conflict
+    let mut hotkeys = Hotkeys::default();
+    hotkeys.apply("pause=Return").unwrap();
+    let keymap: Keymap = "b=Enter".parse().unwrap();
+    let key = |key: &str| Some(if key == "Return" { "Enter" } else { key }.to_string());
+    assert_eq!(hotkeys.conflicts(&keymap, key), vec![("Return".to_string(), Command::Pause, 0xb)]);
+}
+
+#[test]
+fn rebind_from_list() {
+    let mut hotkeys = Hotkeys::default();
+    hotkeys.apply("pause=P, reset=F6").unwrap();
+    assert_eq!(hotkeys.command("P"), Some(Command::Pause));
+    assert_eq!(hotkeys.command("F6"), Some(Command::Reset));
+    assert_eq!(hotkeys.bindings().iter().filter(|(key, _)| key == "F6").count(), 1);
+
+    assert!(hotkeys.apply("rewind=F1").is_err());
+    assert!(hotkeys.apply("pause").is_err());
+    assert_eq!("frame_advance".parse::<Command>(), Ok(Command::FrameAdvance));
+    assert_eq!(Command::FastForward.name(), "fast_forward");
+}
+
+#[test]
+fn restart_clears_recording() {
+    let rom = [0x12, 0x00];
+    let mut session = Session::Record(Movie::new(&rom, &Config::ch8()));
+    session.next_keys([5].into_iter().collect());
+    session.next_keys(Keys::NONE);
+    session.restart(&rom, &Config::ch8());
+    let Session::Record(movie) = session else { unreachable!() };
+    assert!(movie.frames().is_empty());
+}
+
+#[test]
+fn menu_wraps_around() {
+    let mut menu = Menu::new("Hard reset as", vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+    menu.up();
+    assert_eq!(menu.selected, 2);
+    menu.down();
+    menu.down();
+    assert_eq!(menu.selected, 1);
+}
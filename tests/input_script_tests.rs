@@ -0,0 +1,58 @@
+use yac8::chip8::{Chip8, Config};
+use yac8::chip8_runner::HeadlessRunner;
+use yac8::input_script::{InputScript, Timing};
+use yac8::keys::Keys;
+
+// V1 = key after FX0A, then loop forever
+const WAIT_FOR_KEY: [u8; 4] = [0xF1, 0x0A, 0x12, 0x02];
+
+#[test]
+fn parse_script() {
+    let mut parsed: InputScript = "frame 1: press 5; cycle 20: release 5; frame 3: press a".parse().unwrap();
+    let mut built = InputScript::new()
+        .press(Timing::Frame(1), 5)
+        .release(Timing::Cycle(20), 5)
+        .press(Timing::Frame(3), 0xa);
+
+    for (frame, cycle) in [(0, 0), (1, 8), (2, 16), (2, 20), (3, 24)] {
+        assert_eq!(parsed.keys_at(frame, cycle), built.keys_at(frame, cycle));
+    }
+    assert_eq!(parsed.keys_at(3, 24), [0xa].into_iter().collect());
+}
+
+#[test]
+fn keys_are_held_together() {
+    let mut script: InputScript = "frame 1: press 5; frame 1: press c; frame 2: release 5".parse().unwrap();
+    assert_eq!(script.keys_at(0, 0), Keys::NONE);
+    assert_eq!(script.keys_at(1, 8), [0x5, 0xc].into_iter().collect());
+    assert_eq!(script.keys_at(2, 16), [0xc].into_iter().collect());
+}
+
+#[test]
+fn invalid_script() {
+    assert!("frame 1 press 5".parse::<InputScript>().is_err());
+    assert!("frame 1: press 10".parse::<InputScript>().is_err());
+    assert!("second 1: press 1".parse::<InputScript>().is_err());
+}
+
+#[test]
+fn frame_events_fire_once() {
+    let mut script: InputScript = "frame 1: press 5; cycle 10: release 5".parse().unwrap();
+    assert_eq!(script.keys_at(1, 8), [0x5].into_iter().collect());
+    assert_eq!(script.keys_at(1, 10), Keys::NONE);
+    assert_eq!(script.keys_at(1, 11), Keys::NONE);
+}
+
+#[test]
+fn fx0a_waits_for_release() {
+    let mut chip8 = Chip8::new(&WAIT_FOR_KEY, Config::ch8()).unwrap();
+    let script: InputScript = "frame 2: press 7".parse().unwrap();
+    HeadlessRunner::new().script(script).max_frames(10).run(&mut chip8);
+    assert!(chip8.blocked);
+
+    let mut chip8 = Chip8::new(&WAIT_FOR_KEY, Config::ch8()).unwrap();
+    let script: InputScript = "frame 2: press 7; frame 4: release 7".parse().unwrap();
+    HeadlessRunner::new().script(script).max_frames(10).run(&mut chip8);
+    assert!(!chip8.blocked);
+    assert_eq!(chip8.get_register(1), 7);
+}
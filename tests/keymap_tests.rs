@@ -0,0 +1,47 @@
+use yac8::cli::parse_args;
+use yac8::keymap::{Keymap, PRESETS};
+
+fn args(list: &[&str]) -> impl Iterator<Item=String> {
+    list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().into_iter()
+}
+
+#[test]
+fn presets_cover_the_keypad() {
+    for name in PRESETS {
+        let keymap = Keymap::preset(name).unwrap();
+        for key in 0..16 {
+            assert!(keymap.bindings().iter().any(|(_, bound)| *bound == key), "{} misses {:X}", name, key);
+        }
+    }
+    assert_eq!(Keymap::default().key("x"), Some(0x0));
+    assert_eq!(Keymap::preset("azerty").unwrap().key("Z"), Some(0x5));
+    assert_eq!(Keymap::preset("arrows").unwrap().key("Up"), Some(0x5));
+    assert!(Keymap::preset("colemak").is_none());
+}
+
+#[test]
+fn several_host_keys_per_key() {
+    let keymap: Keymap = "5=Up, 5=W, a=Space".parse().unwrap();
+    assert_eq!(keymap.key("up"), Some(0x5));
+    assert_eq!(keymap.key("W"), Some(0x5));
+    assert_eq!(keymap.key("Space"), Some(0xa));
+
+    assert_eq!("15=I".parse::<Keymap>().unwrap().key("I"), Some(0x15));
+    assert!("20=Up".parse::<Keymap>().is_err());
+    assert!("g=Up".parse::<Keymap>().is_err());
+    assert!("5".parse::<Keymap>().is_err());
+}
+
+#[test]
+fn rom_actions_and_cli_keys() {
+    let options = parse_args(args(&["rom.ch8", "--keymap", "dvorak", "--keys", "6=Space"])).unwrap();
+    let keymap = options.keymap(&[("up".to_string(), 0x2), ("a".to_string(), 0x5), ("jump".to_string(), 0x1)]).unwrap();
+    assert_eq!(keymap.key("Up"), Some(0x2));
+    // the command line wins over the database
+    assert_eq!(keymap.key("Space"), Some(0x6));
+    assert_eq!(keymap.key(","), Some(0x5));
+    assert_eq!(keymap.key("W"), None);
+    assert!(options.keymap(&[("up".to_string(), 0x20)]).is_err());
+
+    assert!(parse_args(args(&["rom.ch8", "--keymap", "colemak"])).is_err());
+}
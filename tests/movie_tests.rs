@@ -0,0 +1,63 @@
+use yac8::chip8::{Chip8, Config};
+use yac8::chip8_runner::{replay_movie, run_frame};
+use yac8::keys::Keys;
+use yac8::movie::{MAX_FRAMES, Movie, Session};
+use yac8::quirks::Platform;
+use yac8::timing::TimingMode;
+
+// draws a random sprite whenever key 5 is pressed, then loops
+const ROM: [u8; 14] = [0xA2, 0x0C, 0x60, 0x05, 0xE0, 0xA1, 0xC1, 0x3F, 0xD1, 0x15, 0x12, 0x04, 0xF0, 0x90];
+
+#[test]
+fn serialize_and_parse() {
+    let mut movie = Movie::new(&ROM, &Config::ch8().with_seed(7).with_timing(TimingMode::CosmacVip).with_platform(Platform::Chip8E));
+    [&[][..], &[], &[5], &[5], &[0xa, 0x5], &[0x1f], &[]].iter().for_each(|keys| movie.record_frame(keys.iter().copied().collect()));
+
+    let parsed = Movie::parse(&movie.serialize()).unwrap();
+    assert_eq!(parsed.frames(), movie.frames());
+    assert_eq!(parsed.config().seed(), 7);
+    assert_eq!(parsed.config().timing(), TimingMode::CosmacVip);
+    assert_eq!(parsed.config().platform(), Platform::Chip8E);
+    assert!(!parsed.config().cdp1802());
+    assert!(parsed.check_rom(&ROM).is_ok());
+    assert!(parsed.check_rom(&[0x12, 0x00]).is_err());
+    assert!(Movie::parse(&movie.serialize().replace("1 1f", "1 20")).is_err());
+    // the first version had one key per frame and older quirk bits
+    assert!(Movie::parse(&movie.serialize().replace("yac8-movie 2", "yac8-movie 1")).is_err());
+}
+
+#[test]
+fn replay_is_deterministic() {
+    let config = Config::ch8();
+    let mut session = Session::Record(Movie::new(&ROM, &config));
+    let mut recorded = Chip8::new(&ROM, config).unwrap();
+    for frame in 0..120 {
+        let keys = if frame % 7 < 3 { [5].into_iter().collect() } else { Keys::NONE };
+        run_frame(&mut recorded, session.next_keys(keys)).unwrap();
+    }
+
+    let Session::Record(movie) = session else { unreachable!() };
+    let movie = Movie::parse(&movie.serialize()).unwrap();
+    let mut replayed = Chip8::new(&ROM, movie.config()).unwrap();
+    replay_movie(&mut replayed, &movie).unwrap();
+    assert_eq!(replayed.vram, recorded.vram);
+}
+
+#[test]
+fn keeps_cdp1802() {
+    let movie = Movie::new(&ROM, &Config::ch8().with_cdp1802(true));
+    assert!(Movie::parse(&movie.serialize()).unwrap().config().cdp1802());
+    assert!(Movie::parse(&movie.serialize().replace("cdp1802 on\n", "")).is_err());
+}
+
+#[test]
+fn rejects_huge_frame_counts() {
+    let mut movie = Movie::new(&ROM, &Config::ch8());
+    movie.record_frame(Keys::NONE);
+    let text = movie.serialize();
+
+    // the counts are checked before any frame is added
+    assert!(Movie::parse(&text.replace("\n1 -", &format!("\n{} -", MAX_FRAMES + 1))).is_err());
+    assert!(Movie::parse(&text.replace("\n1 -", &format!("\n1 -\n{} 5", MAX_FRAMES))).is_err());
+    assert!(Movie::parse(&text.replace("\n1 -", &format!("\n{} -", usize::MAX))).is_err());
+}
@@ -0,0 +1,41 @@
+use yac8::chip8::{Chip8, Config};
+use yac8::osd::{Corner, glyph, MESSAGE_FRAMES, Osd, text_pixels, text_width};
+
+#[test]
+fn messages_expire() {
+    let chip8 = Chip8::new(&[], Config::ch8()).unwrap();
+    let mut osd = Osd::new();
+    osd.message("State saved to slot 3");
+    assert_eq!(osd.lines(&chip8), vec![(Corner::BottomLeft, "State saved to slot 3".to_string())]);
+
+    for _ in 0..MESSAGE_FRAMES {
+        osd.tick();
+    }
+    assert!(osd.lines(&chip8).is_empty());
+}
+
+#[test]
+fn status_lines() {
+    let chip8 = Chip8::new(&[0x6A, 0x42], Config::ch8()).unwrap();
+    let mut osd = Osd::new();
+    osd.set_stats(60, 700);
+    assert!(osd.lines(&chip8).is_empty());
+
+    osd.show_stats = true;
+    osd.paused = true;
+    osd.show_registers = true;
+    let lines = osd.lines(&chip8);
+    assert_eq!(&lines[..2], &[(Corner::TopLeft, "60 FPS 700 IPS".to_string()), (Corner::TopLeft, "PAUSED".to_string())]);
+    assert_eq!(lines[2], (Corner::TopRight, "PC 0200 I 0000".to_string()));
+    assert_eq!(lines.len(), 7);
+}
+
+#[test]
+fn font() {
+    assert_eq!(glyph('a'), glyph('A'));
+    assert_eq!(glyph('~'), glyph('?'));
+    assert_eq!(text_width("OK"), 7);
+    // 'I' is 9 pixels, '-' 3 of them
+    assert_eq!(text_pixels("I-").count(), 12);
+    assert!(text_pixels("I-").all(|(x, y)| x < 7 && y < 5));
+}
@@ -0,0 +1,45 @@
+use yac8::chip8::Config;
+use yac8::cli::parse_args;
+use yac8::palette::{InvalidColor, Palette, Theme, THEMES};
+
+fn args(list: &[&str]) -> impl Iterator<Item=String> {
+    list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().into_iter()
+}
+
+#[test]
+fn missing_colours_use_the_foreground() {
+    let palette = Palette::new(&[[1, 2, 3], [4, 5, 6]]);
+    assert_eq!(palette.background(), [1, 2, 3, 0xff]);
+    assert_eq!(palette.color(3), [4, 5, 6, 0xff]);
+    assert_eq!(palette.colors().len(), 4);
+}
+
+#[test]
+fn parse_palette() {
+    let palette: Palette = "#000000, #ffb000,102030".parse().unwrap();
+    assert_eq!(palette.foreground(), [0xff, 0xb0, 0x00, 0xff]);
+    assert_eq!(palette.color(2), [0x10, 0x20, 0x30, 0xff]);
+    assert_eq!("#fff".parse::<Palette>(), Err(InvalidColor));
+}
+
+#[test]
+fn themes_cycle() {
+    let mut theme = Theme::default();
+    for _ in 0..THEMES.len() {
+        assert_eq!(theme.name().parse(), Ok(theme));
+        theme = theme.next();
+    }
+    assert_eq!(theme, Theme::default());
+    assert_eq!(Palette::default(), Theme::Classic.palette());
+}
+
+#[test]
+fn cli_colours() {
+    let options = parse_args(args(&["rom.ch8", "--theme", "amber", "--background", "#101010"])).unwrap();
+    let config = options.apply_overrides(Config::ch8());
+    assert_eq!(config.palette().foreground(), Theme::Amber.palette().foreground());
+    assert_eq!(config.palette().background(), [0x10, 0x10, 0x10, 0xff]);
+
+    assert!(parse_args(args(&["rom.ch8", "--theme", "sepia"])).is_err());
+    assert!(parse_args(args(&["rom.ch8", "--foreground", "white"])).is_err());
+}
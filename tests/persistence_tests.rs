@@ -0,0 +1,56 @@
+use yac8::framebuffer::{Framebuffer, Resolution};
+use yac8::persistence::{fade, Persistence, PersistenceFilter};
+
+fn shown(filter: &PersistenceFilter, x: usize, y: usize) -> (u8, u8) {
+    filter.pixels().find(|pixel| (pixel.0, pixel.1) == (x, y)).map(|(_, _, value, intensity)| (value, intensity)).unwrap()
+}
+
+#[test]
+fn parse_modes() {
+    assert_eq!("off".parse(), Ok(Persistence::Off));
+    assert_eq!("deflicker".parse(), Ok(Persistence::Deflicker));
+    assert_eq!("phosphor".parse(), Ok(Persistence::Phosphor(60)));
+    assert_eq!("phosphor:25".parse(), Ok(Persistence::Phosphor(25)));
+    assert!("phosphor:101".parse::<Persistence>().is_err());
+    assert!("blur".parse::<Persistence>().is_err());
+}
+
+#[test]
+fn phosphor_fades_out() {
+    let mut framebuffer = Framebuffer::new(Resolution::Lores);
+    let mut filter = PersistenceFilter::new(Persistence::Phosphor(50));
+    framebuffer.set(2, 3, true);
+    assert!(filter.update(&framebuffer));
+    assert_eq!(shown(&filter, 2, 3), (1, 0xff));
+
+    framebuffer.set(2, 3, false);
+    filter.update(&framebuffer);
+    assert_eq!(shown(&filter, 2, 3), (1, 127));
+    for _ in 0..8 {
+        filter.update(&framebuffer);
+    }
+    assert_eq!(shown(&filter, 2, 3).0, 0);
+    // nothing left to fade
+    assert!(!filter.update(&framebuffer));
+}
+
+#[test]
+fn deflicker_keeps_the_last_frame() {
+    let mut framebuffer = Framebuffer::new(Resolution::Lores);
+    let mut filter = PersistenceFilter::new(Persistence::Deflicker);
+    framebuffer.set(0, 0, true);
+    filter.update(&framebuffer);
+
+    framebuffer.set(0, 0, false);
+    filter.update(&framebuffer);
+    assert_eq!(shown(&filter, 0, 0), (1, 0xff));
+    filter.update(&framebuffer);
+    assert_eq!(shown(&filter, 0, 0).0, 0);
+}
+
+#[test]
+fn fade_mixes_colours() {
+    assert_eq!(fade([0, 0, 0], [200, 100, 50], 0xff), [200, 100, 50]);
+    assert_eq!(fade([0, 0, 0], [200, 100, 50], 0), [0, 0, 0]);
+    assert_eq!(fade([100, 100, 100], [200, 200, 200], 127), [149, 149, 149]);
+}
@@ -0,0 +1,277 @@
+use yac8::chip8::chip8x::CHIP8X_COLORS;
+use yac8::chip8::megachip::Blend;
+use yac8::chip8::{Chip8, Chip8Error, Config};
+use yac8::chip8_runner::{HeadlessRunner, StopReason, run_frame};
+use yac8::framebuffer::Resolution;
+use yac8::keys::Keys;
+use yac8::quirks::Platform;
+
+fn run(rom: &[u8], platform: Platform) -> (Chip8, StopReason) {
+    let mut chip8 = Chip8::new(rom, Config::ch8().with_platform(platform)).unwrap();
+    let summary = HeadlessRunner::new().stop_on_self_jump().max_frames(10).run(&mut chip8);
+    (chip8, summary.reason)
+}
+
+#[test]
+fn chip8x_programs_start_at_0x300() {
+    let (chip8, reason) = run(&[0x13, 0x00], Platform::Chip8X);
+    assert_eq!(reason, StopReason::SelfJump(0x300));
+    assert_eq!(chip8.read_memory(0x300), Some(0x13));
+}
+
+#[test]
+fn chip8x_adds_nibbles() {
+    // V0 = 0x36, V1 = 0x55, V0 += V1 per nibble
+    let (chip8, _) = run(&[0x60, 0x36, 0x61, 0x55, 0x50, 0x11, 0x13, 0x06], Platform::Chip8X);
+    assert_eq!(chip8.get_register(0), 0x03);
+}
+
+#[test]
+fn chip8x_second_keypad() {
+    // V0 = 5, V1 += 1 when key 5 of keypad 2 is held (EXF2), V2 += 1 when it isn't (EXF5), loop
+    let rom = [0x60, 0x05, 0xE0, 0xF2, 0x71, 0x01, 0xE0, 0xF5, 0x72, 0x01, 0x13, 0x0A];
+    for (keys, held) in [(Keys::NONE, false), ([0x15].into_iter().collect(), true), ([0x05].into_iter().collect(), false)] {
+        let mut chip8 = Chip8::new(&rom, Config::ch8().with_platform(Platform::Chip8X)).unwrap();
+        run_frame(&mut chip8, keys).unwrap();
+        assert_eq!((chip8.get_register(1), chip8.get_register(2)), if held { (0, 1) } else { (1, 0) }, "{:?}", keys);
+    }
+}
+
+#[test]
+fn chip8x_colours() {
+    // V0 = 0x11 (columns 1-2), V1 = 0x00 (rows 0-3), V2 = 4 (green), colour the blocks, cycle the background
+    let rom = [0x60, 0x11, 0x61, 0x00, 0x62, 0x04, 0xB0, 0x20, 0x02, 0xA0, 0x13, 0x0A];
+    let (chip8, _) = run(&rom, Platform::Chip8X);
+    let board = chip8.color_board();
+    assert_eq!(board.color(8, 0, true), CHIP8X_COLORS[4]);
+    assert_eq!(board.color(23, 3, true), CHIP8X_COLORS[4]);
+    assert_eq!(board.color(24, 3, true), CHIP8X_COLORS[1]);
+    assert_eq!(board.color(8, 4, true), CHIP8X_COLORS[1]);
+    assert_eq!(board.color(0, 0, false), CHIP8X_COLORS[0]);
+}
+
+#[test]
+fn chip8e_relative_jumps() {
+    // BF02 skips the V0 = 1, V1 = 2, FX1B skips V1 bytes over V2 = 3, then BB02 halts on itself
+    let rom = [0xBF, 0x02, 0x60, 0x01, 0x61, 0x02, 0xF1, 0x1B, 0x62, 0x03, 0xBB, 0x02];
+    let mut chip8 = Chip8::new(&rom, Config::ch8().with_platform(Platform::Chip8E)).unwrap();
+    HeadlessRunner::new().max_cycles(20).run(&mut chip8);
+    assert_eq!((chip8.get_register(0), chip8.get_register(1), chip8.get_register(2)), (0, 2, 0));
+    assert_eq!(chip8.pc(), 0x20A);
+}
+
+#[test]
+fn chip8e_jump_past_memory() {
+    // V0 = 0x10, jump to 0xFFC where F01B skips past the end of memory
+    let mut rom = vec![0; 0xE00];
+    rom[..4].copy_from_slice(&[0x60, 0x10, 0x1F, 0xFC]);
+    rom[0xDFC..0xDFE].copy_from_slice(&[0xF0, 0x1B]);
+    let mut chip8 = Chip8::new(&rom, Config::ch8().with_platform(Platform::Chip8E)).unwrap();
+    let summary = HeadlessRunner::new().max_cycles(20).run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::Error(Chip8Error::InvalidAddress(0x100E)));
+}
+
+#[test]
+fn schip_switches_resolution() {
+    // hires, DXY0 draws the 16x16 sprite at 0x20A, lores, hires again and loop
+    let mut rom = vec![0x00, 0xFF, 0xA2, 0x0A, 0xD0, 0x00, 0x12, 0x06, 0x00, 0x00];
+    rom.extend([0xFF; 32]);
+    let mut chip8 = Chip8::new(&rom, Config::ch8().with_platform(Platform::SuperChip11)).unwrap();
+    HeadlessRunner::new().max_cycles(3).run(&mut chip8);
+    assert_eq!(chip8.vram.resolution(), Resolution::Hires);
+    assert!(chip8.vram.get(15, 15) && !chip8.vram.get(16, 0) && !chip8.vram.get(0, 16));
+
+    let (chip8, reason) = run(&[0x00, 0xFF, 0x00, 0xFE, 0x12, 0x04], Platform::XoChip);
+    assert_eq!(reason, StopReason::SelfJump(0x204));
+    assert_eq!(chip8.vram.resolution(), Resolution::Lores);
+
+    let (_, reason) = run(&[0x00, 0xFF], Platform::CosmacVip);
+    assert_eq!(reason, StopReason::Error(Chip8Error::MachineCodeRoutine(0x00FF)));
+}
+
+#[test]
+fn schip_scrolls() {
+    // draw the top left pixel, scroll down 4, right 4, left 4, right 4 and loop
+    let lores = [0xA2, 0x0E, 0xD0, 0x01, 0x00, 0xC4, 0x00, 0xFB, 0x00, 0xFC, 0x00, 0xFB, 0x12, 0x0C, 0x80];
+    // the same in hires
+    let hires = [0x00, 0xFF, 0xA2, 0x10, 0xD0, 0x01, 0x00, 0xC4, 0x00, 0xFB, 0x00, 0xFC, 0x00, 0xFB, 0x12, 0x0E, 0x80];
+    let lit = |chip8: &Chip8| chip8.vram.iter().filter(|(_, _, value)| *value != 0).map(|(x, y, _)| (x, y)).collect::<Vec<_>>();
+
+    assert_eq!(lit(&run(&lores, Platform::SuperChipModern).0), [(4, 4)]);
+    // SCHIP 1.1 scrolls half as far in lores
+    assert_eq!(lit(&run(&lores, Platform::SuperChip11).0), [(2, 2)]);
+    assert_eq!(lit(&run(&hires, Platform::SuperChip11).0), [(4, 4)]);
+}
+
+#[test]
+fn schip_big_font_flags_and_exit() {
+    // I = big 7, V0 = 0x11 and V1 = 0x22 saved to the flags, cleared, loaded back and exit
+    let rom = [0x6A, 0x07, 0xFA, 0x30, 0x60, 0x11, 0x61, 0x22, 0xF1, 0x75, 0x60, 0x00, 0x61, 0x00, 0xF1, 0x85, 0x00, 0xFD];
+    let (chip8, reason) = run(&rom, Platform::SuperChipModern);
+    assert_eq!(reason, StopReason::Exited);
+    assert_eq!(chip8.pc(), 0x210);
+    assert_eq!((chip8.get_register(0), chip8.get_register(1)), (0x11, 0x22));
+    assert_eq!(chip8.i(), 0x50 + 7 * 10);
+    assert_eq!(chip8.read_memory(chip8.i() + 4), Some(0x06));
+
+    let (_, reason) = run(&[0x00, 0xFD], Platform::CosmacVip);
+    assert_eq!(reason, StopReason::Error(Chip8Error::MachineCodeRoutine(0x00FD)));
+}
+
+#[test]
+fn xochip_long_i_and_register_ranges() {
+    // I = 0x1234, V0..V2 = 1, 2, 3, save V2..V0, load V0..V1, V0 == 3 skips the whole F000 0000
+    let rom = [
+        0xF0, 0x00, 0x12, 0x34, 0x60, 0x01, 0x61, 0x02, 0x62, 0x03, 0x52, 0x02, 0x50, 0x13, 0x30, 0x03,
+        0xF0, 0x00, 0x00, 0x00, 0x12, 0x14,
+    ];
+    let (chip8, reason) = run(&rom, Platform::XoChip);
+    assert_eq!(reason, StopReason::SelfJump(0x214));
+    assert_eq!(chip8.i(), 0x1234);
+    assert_eq!([0x1234, 0x1235, 0x1236].map(|addr| chip8.read_memory(addr)), [Some(3), Some(2), Some(1)]);
+    assert_eq!((chip8.get_register(0), chip8.get_register(1)), (3, 2));
+
+    let (_, reason) = run(&[0xF0, 0x00, 0x12, 0x34], Platform::SuperChipModern);
+    assert_eq!(reason, StopReason::Error(Chip8Error::UnknownOpcode(0xF000)));
+    assert_eq!(run(&rom, Platform::XoChip).0.read_memory(0xFFFF), Some(0));
+    assert_eq!(run(&rom, Platform::SuperChipModern).0.read_memory(0x1000), None);
+}
+
+#[test]
+fn xochip_audio_pattern() {
+    // I = 0x210, load the pattern there, pitch 112 plays 8000 bits per second, sound timer 0xFF
+    let mut rom = vec![0xA2, 0x10, 0xF0, 0x02, 0x60, 0x70, 0xF0, 0x3A, 0x60, 0xFF, 0xF0, 0x18, 0x12, 0x0C, 0x00, 0x00, 0xA0];
+    rom.resize(0x20, 0);
+    let (mut chip8, _) = run(&rom, Platform::XoChip);
+    assert_eq!(chip8.xochip().pitch(), 112);
+    assert_eq!(chip8.xochip().pattern()[0], 0xA0);
+
+    let mut out = [0; 4];
+    chip8.fill_audio(&mut out, 8000);
+    assert_eq!(out, [0xC0, 0x40, 0xC0, 0x40]);
+}
+
+#[test]
+fn xochip_scrolls_up() {
+    // draw a pixel at (0, 4), scroll up 2 and loop
+    let (chip8, _) = run(&[0x60, 0x00, 0x61, 0x04, 0xA2, 0x0C, 0xD0, 0x11, 0x00, 0xD2, 0x12, 0x0A, 0x80], Platform::XoChip);
+    assert!(chip8.vram.get(0, 2) && !chip8.vram.get(0, 4));
+}
+
+#[test]
+fn xochip_planes() {
+    // both planes draw the two sprites at 0x210, the second plane scrolls right, the first one is cleared
+    let rom = [0xF3, 0x01, 0xA2, 0x10, 0xD0, 0x01, 0xF2, 0x01, 0x00, 0xFB, 0xF1, 0x01, 0x00, 0xE0, 0x12, 0x0E, 0x80, 0xC0];
+    let mut chip8 = Chip8::new(&rom, Config::ch8().with_platform(Platform::XoChip)).unwrap();
+    HeadlessRunner::new().max_cycles(3).run(&mut chip8);
+    assert_eq!(chip8.vram.planes(), 2);
+    assert_eq!((chip8.vram.pixel(0, 0), chip8.vram.pixel(1, 0)), (0b11, 0b10));
+
+    let (chip8, reason) = run(&rom, Platform::XoChip);
+    assert_eq!(reason, StopReason::SelfJump(0x20E));
+    assert_eq!(chip8.xochip().planes(), 1);
+    let lit: Vec<_> = chip8.vram.iter().filter(|(_, _, value)| *value != 0).collect();
+    assert_eq!(lit, [(4, 0, 0b10), (5, 0, 0b10)]);
+}
+
+#[test]
+fn chip8e_register_ranges() {
+    // V1 = 7, V2 = 9, store V1..V2 at 0x300, V1 > V2 is false, load V3..V4 from 0x300
+    let rom = [0x61, 0x07, 0x62, 0x09, 0xA3, 0x00, 0x51, 0x22, 0x51, 0x21, 0xA3, 0x00, 0x53, 0x43, 0x12, 0x0E];
+    let (chip8, reason) = run(&rom, Platform::Chip8E);
+    assert_eq!(reason, StopReason::SelfJump(0x20E));
+    assert_eq!((chip8.get_register(3), chip8.get_register(4)), (7, 9));
+}
+
+#[test]
+fn variant_opcodes_need_their_platform() {
+    let (_, reason) = run(&[0x02, 0xA0], Platform::CosmacVip);
+    assert!(matches!(reason, StopReason::Error(_)));
+}
+
+// Enable mega mode, I = 0x220, load one ARGB colour, 2x1 sprites, colour 1 collides, I = 0x224,
+// draw at (3, 1) twice and present. Palette entry 1 is at 0x220, the sprite at 0x224
+const MEGACHIP_DRAW: [u8; 38] = [
+    0x00, 0x11, 0x01, 0x00, 0x02, 0x20, 0x02, 0x01, 0x03, 0x02, 0x04, 0x01, 0x09, 0x01, 0x60, 0x03,
+    0x61, 0x01, 0x01, 0x00, 0x02, 0x24, 0xD0, 0x11, 0xD0, 0x11, 0x00, 0xE0, 0x12, 0x1C, 0x00, 0x00,
+    0xFF, 0x12, 0x34, 0x56, 0x01, 0x00,
+];
+
+#[test]
+fn megachip_draws_with_palette() {
+    let (chip8, reason) = run(&MEGACHIP_DRAW, Platform::MegaChip);
+    assert_eq!(reason, StopReason::SelfJump(0x21C));
+    let mega = chip8.megachip().unwrap();
+    assert!(mega.enabled());
+    assert_eq!(mega.palette()[1], 0xFF123456);
+    assert_eq!(mega.blend(), Blend::Normal);
+    assert_eq!(chip8.vram.resolution(), Resolution::Mega);
+    assert_eq!(chip8.vram.color(3, 1), Some([0x12, 0x34, 0x56, 0xff]));
+    assert_eq!(chip8.vram.color(4, 1), Some([0, 0, 0, 0xff]));
+    // the second draw hits the pixel of the first
+    assert_eq!(chip8.get_register(0xf), 1);
+}
+
+#[test]
+fn megachip_screen_alpha() {
+    // 0580 in place of the collision colour halves the presented colours
+    let mut rom = MEGACHIP_DRAW;
+    rom[12..14].copy_from_slice(&[0x05, 0x80]);
+    let (chip8, _) = run(&rom, Platform::MegaChip);
+    assert_eq!(chip8.megachip().unwrap().alpha(), 0x80);
+    assert_eq!(chip8.vram.color(3, 1), Some([0x09, 0x1a, 0x2b, 0xff]));
+}
+
+#[test]
+fn megachip_plays_samples_past_4k() {
+    // I = 0x001000, play the sample there once
+    let mut rom = vec![0x00, 0x11, 0x01, 0x00, 0x10, 0x00, 0x06, 0x01, 0x12, 0x08];
+    rom.resize(0x1000 - 0x200, 0);
+    rom.extend_from_slice(&[0x1F, 0x40, 0x00, 0x00, 0x02, 0x00, 0x80, 0x90]);
+    let (mut chip8, _) = run(&rom, Platform::MegaChip);
+
+    let sample = *chip8.megachip().unwrap().sample().unwrap();
+    assert_eq!((sample.start, sample.length, sample.rate, sample.looping), (0x1000, 2, 8000, false));
+    let mut out = [0; 4];
+    chip8.fill_megachip_audio(&mut out, 8000);
+    assert_eq!(out, [0x80, 0x90, 0x80, 0x80]);
+}
+
+#[test]
+fn megachip_plays_samples_changed_by_the_program() {
+    // writes 0xA0 over the first sample at 0x306, then plays the sample at 0x300
+    let mut rom = vec![0xA3, 0x06, 0x60, 0xA0, 0xF0, 0x55, 0xA3, 0x00, 0x06, 0x01, 0x12, 0x0A];
+    rom.resize(0x300 - 0x200, 0);
+    rom.extend_from_slice(&[0x1F, 0x40, 0x00, 0x00, 0x02, 0x00, 0x80, 0x90]);
+    let (mut chip8, _) = run(&rom, Platform::MegaChip);
+
+    let mut out = [0; 4];
+    chip8.fill_megachip_audio(&mut out, 8000);
+    assert_eq!(out, [0xA0, 0x90, 0x80, 0x80]);
+}
+
+#[test]
+fn roms_must_fit_into_memory() {
+    let rom = [0x12, 0x00].repeat(0xA00);
+    assert_eq!(Chip8::new(&rom[..0xE00], Config::ch8()).map(|chip8| chip8.read_memory(0xFFF)).unwrap(), Some(0x00));
+    assert_eq!(Chip8::new(&rom, Config::ch8()).err(), Some(Chip8Error::RomTooLarge(0x1400)));
+    assert_eq!(Chip8::new(&rom[..0xE00], Config::ch8().with_platform(Platform::Chip8X)).err(), Some(Chip8Error::RomTooLarge(0xE00)));
+    assert!(Chip8::new(&rom, Config::ch8().with_platform(Platform::MegaChip)).is_ok());
+}
+
+#[test]
+fn megachip_long_i_at_the_end_of_memory() {
+    // 0100 at 0xFFE has its low word past the end
+    let mut rom = vec![0x1F, 0xFE];
+    rom.resize(0xFFE - 0x200, 0);
+    rom.extend_from_slice(&[0x01, 0x00]);
+    let (_, reason) = run(&rom, Platform::MegaChip);
+    assert_eq!(reason, StopReason::Error(Chip8Error::InvalidAddress(0x1000)));
+}
+
+#[test]
+fn megachip_needs_its_platform() {
+    let (chip8, reason) = run(&[0x00, 0x11, 0x12, 0x02], Platform::SuperChip11);
+    assert!(chip8.megachip().is_none());
+    assert!(matches!(reason, StopReason::Error(_)));
+}
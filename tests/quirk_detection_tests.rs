@@ -0,0 +1,88 @@
+use yac8::quirk_detection::{detect, detect_with_execution, Suggestion};
+use yac8::quirks::{Platform, CH8_QUIRKS, SCHIP_QUIRKS};
+
+#[test]
+fn plain_chip8() {
+    // CLS, V0 = 1, loop
+    let report = detect(&[0x00, 0xE0, 0x60, 0x01, 0x12, 0x04]);
+    assert_eq!(report.platform, Platform::CosmacVip);
+    assert_eq!(report.quirks, CH8_QUIRKS);
+    assert!(report.evidence.is_empty());
+}
+
+#[test]
+fn superchip_opcode() {
+    // hires, loop
+    let report = detect(&[0x00, 0xFF, 0x12, 0x02]);
+    assert_eq!(report.platform, Platform::SuperChipModern);
+    assert_eq!(report.evidence[0].addr, 0x200);
+    assert_eq!(report.evidence[0].suggestion, Suggestion::Platform(Platform::SuperChipModern));
+}
+
+#[test]
+fn data_is_not_scanned() {
+    // jump over 00FF stored as data
+    let report = detect(&[0x12, 0x04, 0x00, 0xFF, 0x12, 0x04]);
+    assert_eq!(report.platform, Platform::CosmacVip);
+}
+
+#[test]
+fn quirk_evidence() {
+    let rom = [
+        0x81, 0x26, // V1 = V2 >> 1
+        0xA3, 0x00, // I = 0x300
+        0xF1, 0x55, // dump V0..V1
+        0xF1, 0x65, // load V0..V1 again from the same I
+        0x62, 0x04, // V2 = 4
+        0xB2, 0x20, // jump to 0x220 + V2
+    ];
+    let report = detect(&rom);
+    let suggestions: Vec<Suggestion> = report.evidence.iter().map(|evidence| evidence.suggestion).collect();
+    assert!(suggestions.contains(&Suggestion::Quirk("shifting", false)));
+    assert!(suggestions.contains(&Suggestion::Quirk("memory", false)));
+    assert!(suggestions.contains(&Suggestion::Quirk("jumping", true)));
+    assert!(report.to_string().contains("I is used again at 0x206"));
+}
+
+#[test]
+fn speculative_execution() {
+    // the jump only lands on the loop with the SCHIP jump quirk, otherwise it returns without a call
+    let rom = [
+        0x62, 0x04, // V2 = 4
+        0x63, 0x00, 0x63, 0x00, 0x63, 0x00, 0x63, 0x00, // padding so V2 is out of the static lookbehind
+        0xB2, 0x0C, // jump to 0x20c + V2 (SCHIP) or V0 (CHIP-8)
+        0x00, 0xEE, // 0x20c: return without call
+        0x00, 0xE0, // 0x20e: unused
+        0x12, 0x10, // 0x210: loop
+    ];
+    assert_eq!(detect(&rom).platform, Platform::CosmacVip);
+
+    let report = detect_with_execution(&rom, 10);
+    assert_eq!(report.platform, Platform::SuperChipModern);
+    assert_eq!(report.quirks, SCHIP_QUIRKS);
+    assert!(report.to_string().contains("Stack underflow"));
+}
+
+#[test]
+fn schip_instructions_run_in_the_schip_profile() {
+    let rom = [
+        0x30, 0x00, // skip the next instruction when V0 is 0
+        0x00, 0xFF, // hires, only seen by the static scan
+        0xD0, 0x01, // draw, waits for the next frame on the VIP
+        0xD0, 0x01,
+        0xF0, 0x75, // store V0 in the HP 48 flags
+        0x12, 0x0A, // loop
+    ];
+    let report = detect_with_execution(&rom, 1);
+    assert_eq!(report.platform, Platform::SuperChipModern);
+    assert!(!report.to_string().contains("stopped with"));
+}
+
+#[test]
+fn code_running_to_the_end_of_memory() {
+    // skips and stores all the way up to 0xFFFE
+    let rom: Vec<u8> = [0x30, 0x00, 0xF1, 0x55].iter().copied().cycle().take(0x10000 - 0x200).collect();
+    let report = detect(&rom);
+    assert_eq!(report.platform, Platform::CosmacVip);
+    assert!(report.to_string().contains("I is used again at 0xfffe"));
+}
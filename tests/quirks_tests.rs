@@ -0,0 +1,95 @@
+use yac8::chip8::{Chip8, Config};
+use yac8::chip8_runner::HeadlessRunner;
+use yac8::quirks::{CH8_QUIRKS, CHIP48_QUIRKS, Quirks, SCHIP10_QUIRKS, SCHIP_QUIRKS, UnknownQuirk};
+
+// I = 0x300, FX55 with X = 2, then stores 0xAA at the resulting I
+const MEMORY_INCREMENT: [u8; 10] = [0xA3, 0x00, 0xF2, 0x55, 0x60, 0xAA, 0xF0, 0x55, 0x12, 0x08];
+// I = 0xFFF, I += 1
+const I_OVERFLOW: [u8; 8] = [0xAF, 0xFF, 0x60, 0x01, 0xF0, 0x1E, 0x12, 0x06];
+// DXY0 with I pointing at the font
+const BIG_SPRITE: [u8; 6] = [0xA0, 0x00, 0xD0, 0x00, 0x12, 0x04];
+
+fn run(rom: &[u8], quirks: Quirks) -> Chip8 {
+    let mut chip8 = Chip8::new(rom, Config::ch8().with_quirks(quirks)).unwrap();
+    HeadlessRunner::new().stop_on_self_jump().max_frames(10).run(&mut chip8);
+    chip8
+}
+
+#[test]
+fn memory_increment_by_x() {
+    assert_eq!(run(&MEMORY_INCREMENT, CH8_QUIRKS).read_memory(0x303), Some(0xAA));
+    assert_eq!(run(&MEMORY_INCREMENT, CHIP48_QUIRKS).read_memory(0x302), Some(0xAA));
+}
+
+#[test]
+fn i_overflow_sets_vf() {
+    assert_eq!(run(&I_OVERFLOW, CH8_QUIRKS).get_register(0xf), 0);
+
+    let quirks = Quirks::from_bits(CH8_QUIRKS.to_bits() | 1 << 8);
+    assert_eq!(run(&I_OVERFLOW, quirks).get_register(0xf), 1);
+}
+
+#[test]
+fn dxy0_sprite_size() {
+    let lit = |chip8: &Chip8, column: usize| chip8.vram.iter().filter(|(x, _, value)| (column..column + 8).contains(x) && *value != 0).count();
+
+    let chip8 = run(&BIG_SPRITE, CH8_QUIRKS);
+    assert_eq!(lit(&chip8, 0), 0);
+
+    let chip8 = run(&BIG_SPRITE, SCHIP10_QUIRKS);
+    assert!((0..8).any(|x| chip8.vram.get(x, 15)));
+    assert_eq!(lit(&chip8, 8), 0);
+
+    let chip8 = run(&BIG_SPRITE, SCHIP_QUIRKS);
+    assert_ne!(lit(&chip8, 8), 0);
+}
+
+#[test]
+fn quirk_bits_round_trip() {
+    for quirks in [CH8_QUIRKS, CHIP48_QUIRKS, SCHIP10_QUIRKS, SCHIP_QUIRKS] {
+        assert_eq!(Quirks::from_bits(quirks.to_bits()), quirks);
+    }
+}
+
+#[test]
+fn builder() {
+    let quirks = Quirks::builder().shifting(true).clipping(true).build();
+    assert_eq!(quirks.get("shifting"), Some(true));
+    assert_eq!(quirks.get("clipping_y"), Some(true));
+    assert_eq!(quirks.get("memory"), Some(false));
+    assert_eq!(quirks.get("unknown"), None);
+
+    assert_eq!(CH8_QUIRKS.to_builder().memory_increment_by_x(true).build().diff(&CH8_QUIRKS).count(), 1);
+}
+
+#[test]
+fn compact_string() {
+    let quirks: Quirks = "vf_reset, -memory,shifting".parse().unwrap();
+    assert_eq!(quirks, Quirks::builder().vf_reset(true).shifting(true).build());
+    assert_eq!(quirks.to_string().parse::<Quirks>(), Ok(quirks));
+
+    assert_eq!("schip,-shifting".parse::<Quirks>(), Ok(SCHIP_QUIRKS.to_builder().shifting(false).build()));
+    assert_eq!("shifting,wrap".parse::<Quirks>(), Err(UnknownQuirk));
+}
+
+#[test]
+fn diff() {
+    let diff: Vec<_> = CH8_QUIRKS.diff(&CHIP48_QUIRKS).collect();
+    assert_eq!(diff, vec![
+        ("vf_reset", true, false),
+        ("shifting", false, true),
+        ("jumping", false, true),
+        ("display_wait", true, false),
+        ("memory_increment_by_x", false, true),
+    ]);
+    assert_eq!(SCHIP_QUIRKS.diff(&SCHIP_QUIRKS).count(), 0);
+}
+
+#[test]
+fn serde_round_trip() {
+    let json = serde_json::to_string(&SCHIP10_QUIRKS).unwrap();
+    assert_eq!(serde_json::from_str::<Quirks>(&json).unwrap(), SCHIP10_QUIRKS);
+
+    let partial: Quirks = serde_json::from_str(r#"{ "jumping": true }"#).unwrap();
+    assert_eq!(partial, Quirks::builder().jumping(true).build());
+}
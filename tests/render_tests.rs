@@ -0,0 +1,30 @@
+use yac8::cli::parse_args;
+use yac8::render::{letterbox, WindowSettings};
+
+#[test]
+fn integer_scale_fills_the_window() {
+    assert_eq!(letterbox((1280, 640), (64, 32)), (0, 0, 1280, 640));
+    assert_eq!(letterbox((1280, 640), (128, 64)), (0, 0, 1280, 640));
+}
+
+#[test]
+fn letterboxes_the_rest() {
+    // 15x fits horizontally, the height limits it to 10x
+    assert_eq!(letterbox((1000, 350), (64, 32)), (180, 15, 640, 320));
+    assert_eq!(letterbox((800, 800), (256, 192)), (16, 112, 768, 576));
+}
+
+#[test]
+fn tiny_windows_keep_one_pixel_per_pixel() {
+    assert_eq!(letterbox((32, 16), (64, 32)), (-16, -8, 64, 32));
+}
+
+#[test]
+fn window_options() {
+    let args = ["rom.ch8", "--scale", "8", "--grid"].iter().map(|arg| arg.to_string());
+    let window = parse_args(args).unwrap().window;
+    assert_eq!(window, WindowSettings { scale: 8, fullscreen: false, grid: true });
+
+    let args = ["rom.ch8", "--scale", "0"].iter().map(|arg| arg.to_string());
+    assert!(parse_args(args).is_err());
+}
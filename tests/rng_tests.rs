@@ -0,0 +1,71 @@
+use yac8::chip8::{Chip8, Config};
+use yac8::chip8_runner::run_for_cycles;
+use yac8::rng::{DefaultRng, Rng, RngMode, VipRng};
+
+// V0 = rand() & 0x0F, V1 = rand() & 0xF0, then loop forever
+const RAND_ROM: [u8; 6] = [0xC0, 0x0F, 0xC1, 0xF0, 0x12, 0x04];
+
+struct ScriptedRng {
+    values: Vec<u8>,
+}
+
+impl Rng for ScriptedRng {
+    fn next_u8(&mut self) -> u8 {
+        self.values.remove(0)
+    }
+}
+
+#[test]
+fn scripted_rng() {
+    let rng = ScriptedRng { values: vec![0xAB, 0xCD] };
+    let mut chip8 = Chip8::with_rng(&RAND_ROM, Config::ch8(), rng).unwrap();
+    run_for_cycles(&mut chip8, 2);
+    assert_eq!(chip8.get_register(0), 0x0B);
+    assert_eq!(chip8.get_register(1), 0xC0);
+}
+
+#[test]
+fn same_seed_same_values() {
+    for mode in [RngMode::XorShift, RngMode::CosmacVip] {
+        let mut first = Chip8::new(&RAND_ROM, Config::ch8().with_seed(1234).with_rng_mode(mode)).unwrap();
+        let mut second = Chip8::new(&RAND_ROM, Config::ch8().with_seed(1234).with_rng_mode(mode)).unwrap();
+        run_for_cycles(&mut first, 2);
+        run_for_cycles(&mut second, 2);
+        assert_eq!(first.get_register(0), second.get_register(0));
+        assert_eq!(first.get_register(1), second.get_register(1));
+    }
+}
+
+#[test]
+fn rng_state_is_saved_with_chip8() {
+    for mode in [RngMode::XorShift, RngMode::CosmacVip] {
+        let mut chip8 = Chip8::new(&[0xC0, 0xFF, 0x12, 0x00], Config::ch8().with_seed(42).with_rng_mode(mode)).unwrap();
+        run_for_cycles(&mut chip8, 10);
+        let state = chip8.save_state().unwrap();
+        let mut loaded: Chip8 = Chip8::load_state(&state).unwrap();
+        assert_eq!(loaded.save_state().unwrap(), state);
+
+        let mut values = Vec::new();
+        for chip8 in [&mut chip8, &mut loaded] {
+            run_for_cycles(chip8, 10);
+            values.push((chip8.pc(), chip8.get_register(0), chip8.save_state().unwrap()));
+        }
+        assert_eq!(values[0], values[1]);
+    }
+    assert!(Chip8::<DefaultRng>::load_state("{}").is_err());
+}
+
+#[test]
+fn vip_routine() {
+    // with page[n] = n from R9 = 0: 0 + 1 -> 1, 1 + 2 -> 3 / 2 + 3 = 4, 4 + 3 -> 7 / 2 + 7 = 10, ...
+    let mut rng = VipRng::with_page(0, Box::leak(Box::new(std::array::from_fn(|n| n as u8))));
+    assert_eq!([(); 4].map(|_| rng.next_u8()), [0x01, 0x04, 0x0a, 0x15]);
+
+    // 0x80 + 0xff carries into the top bit of the rotated sum: 0xbf + 0x7f
+    let mut rng = VipRng::with_page(0x8000, &[0xff; 256]);
+    assert_eq!(rng.next_u8(), 0x3e);
+
+    // R9.0 wraps into R9.1 before the page is read
+    let mut rng = VipRng::with_page(0x00ff, &[0; 256]);
+    assert_eq!(rng.next_u8(), 0x01);
+}
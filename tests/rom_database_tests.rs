@@ -0,0 +1,61 @@
+use yac8::quirks::{CH8_QUIRKS, Platform, SCHIP11_QUIRKS};
+use yac8::rom_database::{rom_hash, RomDatabase};
+
+const ROM: [u8; 2] = [0x12, 0x00];
+const HYBRID: [u8; 4] = [0x02, 0x04, 0x12, 0x02];
+
+fn database() -> RomDatabase {
+    let json = format!(r##"[
+        {{
+            "title": "Test Rom",
+            "roms": {{
+                "{}": {{
+                    "platforms": ["futurechip", "superchip"],
+                    "quirkyPlatforms": {{ "superchip": {{ "shift": false, "wrap": true }} }},
+                    "tickrate": 30,
+                    "colors": {{ "pixels": ["#000000", "#ff8000"] }},
+                    "keys": {{ "up": 5, "down": 8 }}
+                }}
+            }}
+        }},
+        {{ "title": "Hybrid", "roms": {{ "{}": {{ "platforms": ["hybridVIP"] }} }} }},
+        {{ "title": "Unsupported", "roms": {{ "0000": {{ "platforms": ["futurechip"] }} }} }}
+    ]"##, rom_hash(&ROM).to_uppercase(), rom_hash(&HYBRID));
+    RomDatabase::parse(&json).unwrap()
+}
+
+#[test]
+fn lookup_by_hash() {
+    let database = database();
+    assert_eq!(database.len(), 2);
+
+    let info = database.lookup(&ROM).unwrap();
+    assert_eq!(info.title, "Test Rom");
+    assert_eq!(info.platform, Platform::SuperChip11);
+    assert_eq!(info.tickrate, Some(30));
+    assert_eq!(info.colors, vec![[0, 0, 0], [0xff, 0x80, 0]]);
+    assert!(info.keys.contains(&("up".to_string(), 5)));
+    assert_eq!(info.config().cycles_per_frame(), 30);
+    assert_eq!(info.config().palette().foreground(), [0xff, 0x80, 0, 0xff]);
+
+    assert!(database.lookup(&[0x00, 0xE0]).is_none());
+    assert!(!info.config().cdp1802());
+    assert!(database.lookup(&HYBRID).unwrap().config().cdp1802());
+}
+
+#[test]
+fn quirky_platform_overrides() {
+    let info = database().lookup(&ROM).unwrap().clone();
+    assert_ne!(info.quirks, SCHIP11_QUIRKS);
+    assert_eq!(info.config().quirks(), &info.quirks);
+}
+
+#[test]
+fn builtin_database() {
+    let rom = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/roms/chip8-test-suite.ch8")).unwrap();
+    assert_eq!(rom_hash(&rom), "5a8e1cada60dddd388ac954852aac63f284589ff");
+    let info = RomDatabase::builtin().lookup(&rom).cloned().unwrap();
+    assert_eq!(info.title, "CHIP-8 test suite");
+    assert_eq!(info.platform, Platform::CosmacVip);
+    assert_eq!(info.quirks, CH8_QUIRKS);
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use yac8::rom_database::RomDatabase;
+use yac8::rom_loader::{load_roms, RomFile, unpack};
+use yac8::rom_picker::RomPicker;
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in files {
+        writer.start_file(*name, SimpleFileOptions::default()).unwrap();
+        writer.write_all(data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("yac8-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn unpack_archives() {
+    let roms = unpack("pong.ch8.gz", gzip(&[0x12, 0x00])).unwrap();
+    assert_eq!(roms, vec![RomFile { name: "pong.ch8".to_string(), data: vec![0x12, 0x00] }]);
+
+    let pack = zip(&[("games/b.ch8", &[0x00, 0xe0]), ("readme.txt", b"hello"), ("a.sc8.gz", &gzip(&[0x00, 0xff]))]);
+    let roms = unpack("pack.ZIP", pack).unwrap();
+    let names: Vec<_> = roms.iter().map(|rom| rom.name.as_str()).collect();
+    assert_eq!(names, ["games/b.ch8", "a.sc8"]);
+    assert_eq!(roms[1].data, [0x00, 0xff]);
+
+    assert!(unpack("broken.zip", vec![1, 2, 3]).is_err());
+    assert!(unpack("broken.gz", vec![1, 2, 3]).is_err());
+    assert_eq!(unpack("game.rom", vec![0x12]).unwrap().len(), 1);
+}
+
+#[test]
+fn load_directory() {
+    let dir = temp_dir("roms");
+    fs::create_dir(dir.join("schip")).unwrap();
+    fs::write(dir.join("b.ch8"), [0x12, 0x00]).unwrap();
+    fs::write(dir.join("notes.md"), "# roms").unwrap();
+    fs::write(dir.join("schip").join("c.sc8.gz"), gzip(&[0x00, 0xff])).unwrap();
+    fs::write(dir.join("a.zip"), zip(&[("z.xo8", &[0xf0, 0x00])])).unwrap();
+
+    let names: Vec<_> = load_roms(&dir).unwrap().into_iter().map(|rom| rom.name).collect();
+    assert_eq!(names, ["b.ch8", "schip/c.sc8", "z.xo8"]);
+    assert_eq!(load_roms(&dir.join("b.ch8")).unwrap()[0].data, [0x12, 0x00]);
+    assert!(load_roms(&dir.join("missing.ch8")).is_err());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn pick_with_search() {
+    let rom = |name: &str, data: &[u8]| RomFile { name: name.to_string(), data: data.to_vec() };
+    let roms = [rom("space invaders.ch8", &[0x12, 0x00]), rom("tetris.ch8", &[0x12, 0x02]), rom("tank.ch8", &[0x12, 0x04])];
+    let picker = RomPicker::new(&roms, &RomDatabase::parse("[]").unwrap());
+    assert_eq!(picker.titles()[1], "tetris.ch8");
+    assert_eq!(picker.filter("T"), [1, 2]);
+    assert_eq!(picker.filter("space ch8"), [0]);
+
+    let mut output = Vec::new();
+    assert_eq!(picker.pick(Cursor::new("ta\n1\n"), &mut output).unwrap(), Some(2));
+    assert!(String::from_utf8(output).unwrap().contains("   1 tank.ch8"));
+    assert_eq!(picker.pick(Cursor::new("9\n\n2\n"), Vec::new()).unwrap(), Some(1));
+    assert_eq!(picker.pick(Cursor::new("q\n"), Vec::new()).unwrap(), None);
+    assert_eq!(picker.pick(Cursor::new(""), Vec::new()).unwrap(), None);
+}
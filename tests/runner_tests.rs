@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use yac8::chip8::{Chip8, Chip8Error, Config};
+use yac8::chip8_runner::{HeadlessRunner, StopReason, DEFAULT_MAX_FRAMES};
+use yac8::cli::parse_args;
+use yac8::timing::TimingMode;
+
+// V0 += 1, [0x300] = V0 via FX55, jump back to start
+const COUNTER: [u8; 8] = [0x70, 0x01, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x00];
+// draws the 0 glyph, then jumps to itself
+const DRAW_AND_HALT: [u8; 6] = [0xA0, 0x00, 0xD0, 0x05, 0x12, 0x04];
+
+#[test]
+fn stops_on_self_jump() {
+    let mut chip8 = Chip8::new(&DRAW_AND_HALT, Config::ch8()).unwrap();
+    let summary = HeadlessRunner::new().stop_on_self_jump().max_frames(100).run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::SelfJump(0x204));
+    assert_eq!(chip8.pc(), 0x204);
+}
+
+#[test]
+fn stops_on_cycles_beyond_u16() {
+    let mut chip8 = Chip8::new(&COUNTER, Config::ch8()).unwrap();
+    let summary = HeadlessRunner::new().max_cycles(100_000).run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::Cycles);
+    assert_eq!(summary.cycles, 100_000);
+    assert_eq!(summary.frames, 100_000 / 8 - 1);
+}
+
+#[test]
+fn stops_on_frames() {
+    let mut chip8 = Chip8::new(&COUNTER, Config::ch8()).unwrap();
+    let summary = HeadlessRunner::new().max_frames(3).run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::Frames);
+    assert_eq!(summary.cycles, 24);
+}
+
+#[test]
+fn stops_after_the_default_frames() {
+    let mut chip8 = Chip8::new(&COUNTER, Config::ch8()).unwrap();
+    let summary = HeadlessRunner::new().run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::Frames);
+    assert_eq!(summary.frames, DEFAULT_MAX_FRAMES);
+}
+
+#[test]
+fn stops_at_pc() {
+    let mut chip8 = Chip8::new(&COUNTER, Config::ch8()).unwrap();
+    let summary = HeadlessRunner::new().stop_at_pc(0x204).run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::PcReached(0x204));
+    assert_eq!(summary.cycles, 2);
+}
+
+#[test]
+fn stops_on_memory() {
+    let mut chip8 = Chip8::new(&COUNTER, Config::ch8()).unwrap();
+    let summary = HeadlessRunner::new().stop_on_memory(0x300, 42).run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::MemoryMatched(0x300, 42));
+    assert_eq!(chip8.get_register(0), 42);
+}
+
+#[test]
+fn stops_on_vram_hash() {
+    let mut expected = Chip8::new(&DRAW_AND_HALT, Config::ch8()).unwrap();
+    HeadlessRunner::new().stop_on_self_jump().run(&mut expected);
+
+    let mut chip8 = Chip8::new(&DRAW_AND_HALT, Config::ch8()).unwrap();
+    let summary = HeadlessRunner::new().stop_on_vram_hash(expected.vram_hash()).max_frames(100).run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::VramMatched(expected.vram_hash()));
+    assert_eq!(chip8.vram, expected.vram);
+}
+
+#[test]
+fn stops_on_error() {
+    let mut chip8 = Chip8::new(&[0x00, 0xEE], Config::ch8()).unwrap();
+    let summary = HeadlessRunner::new().max_frames(1).run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::Error(Chip8Error::StackUnderflow));
+
+    let mut chip8 = Chip8::new(&[0xFF, 0xFF], Config::ch8()).unwrap();
+    let summary = HeadlessRunner::new().max_frames(1).run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::Error(Chip8Error::UnknownOpcode(0xFFFF)));
+}
+
+#[test]
+fn stops_at_the_end_of_memory() {
+    // jumps to the last byte, the self jump check and the fetch both read past it
+    let mut chip8 = Chip8::new(&[0x1F, 0xFF], Config::ch8()).unwrap();
+    let summary = HeadlessRunner::new().stop_on_self_jump().max_frames(1).run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::Error(Chip8Error::InvalidAddress(0xFFF)));
+}
+
+#[test]
+fn memory_past_the_end_is_an_error() {
+    // I = 0xFFE or 0xFFA, then FX33, F755, F765 and a 5 row sprite run past 0xFFF
+    for rom in [[0xAF, 0xFE, 0xF0, 0x33], [0xAF, 0xFA, 0xF7, 0x55], [0xAF, 0xFA, 0xF7, 0x65], [0xAF, 0xFE, 0xD0, 0x15]] {
+        let mut chip8 = Chip8::new(&rom, Config::ch8()).unwrap();
+        let summary = HeadlessRunner::new().max_frames(2).run(&mut chip8);
+        assert_eq!(summary.reason, StopReason::Error(Chip8Error::InvalidAddress(0x1000)), "{:02X?}", rom);
+    }
+
+    let mut chip8 = Chip8::new(&COUNTER, Config::ch8()).unwrap();
+    assert_eq!(chip8.read_memory(0x1000), None);
+    let summary = HeadlessRunner::new().stop_on_memory(0x1000, 0).max_frames(1).run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::Frames);
+}
+
+#[test]
+fn tickrate_must_run_something() {
+    let args = |tickrate: &str| ["rom.ch8", "--tickrate", tickrate].map(str::to_string).into_iter();
+    assert_eq!(parse_args(args("30")).unwrap().tickrate, Some(30));
+    assert!(parse_args(args("0")).is_err());
+}
+
+#[test]
+fn stops_on_timeout() {
+    let mut chip8 = Chip8::new(&COUNTER, Config::ch8()).unwrap();
+    let summary = HeadlessRunner::new().timeout(Duration::from_millis(10)).max_frames(u64::MAX).run(&mut chip8);
+    assert_eq!(summary.reason, StopReason::Timeout);
+    assert!(summary.elapsed >= Duration::from_millis(10));
+}
+
+#[test]
+fn vip_timing_charges_machine_cycles() {
+    // 7XNN and 1NNN cost 50 and 62 machine cycles out of the 1836 left per frame
+    let mut chip8 = Chip8::new(&[0x70, 0x01, 0x12, 0x00], Config::ch8().with_timing(TimingMode::CosmacVip)).unwrap();
+    let summary = HeadlessRunner::new().max_frames(1).run(&mut chip8);
+    assert_eq!(summary.cycles, 33);
+    assert_eq!(chip8.get_register(0), 17);
+}
@@ -0,0 +1,104 @@
+use std::fs;
+
+use yac8::chip8::Config;
+use yac8::cli::{parse_args, Options};
+use yac8::persistence::Persistence;
+use yac8::quirks::{Platform, Quirks};
+use yac8::settings::{QuirkSetting, Settings};
+
+fn options(settings: &Settings, cli: &[&str]) -> Result<Options, String> {
+    parse_args(settings.to_args().into_iter().chain(cli.iter().map(|arg| arg.to_string())))
+}
+
+#[test]
+fn config_file_becomes_flags() {
+    let settings = Settings::parse(r#"{
+        "platform": "schip",
+        "tickrate": 30,
+        "quirks": "-shifting",
+        "persistence": "phosphor:80",
+        "keymap": "arrows",
+        "grid": true,
+        "fullscreen": false
+    }"#).unwrap();
+    let options = options(&settings, &["rom.ch8"]).unwrap();
+    assert_eq!(options.platform, Some(Platform::SuperChipModern));
+    assert_eq!(options.tickrate, Some(30));
+    assert_eq!(options.quirks.as_deref(), Some("-shifting"));
+    assert_eq!(options.persistence, Some(Persistence::Phosphor(80)));
+    assert!(options.window.grid && !options.window.fullscreen);
+}
+
+#[test]
+fn switches_turn_off() {
+    let settings = Settings::parse(r#"{ "grid": true, "cdp1802": true, "roms": { "0000": { "grid": false, "cdp1802": false } } }"#).unwrap();
+    assert_eq!(settings.to_args(), ["--cdp1802", "--grid"]);
+    assert!(!options(&settings, &["rom.ch8", "--no-grid"]).unwrap().window.grid);
+
+    let options = parse_args(settings.args_for("0000").into_iter().chain(["rom.ch8".to_string()])).unwrap();
+    assert!(!options.window.grid);
+    assert_eq!(options.cdp1802, Some(false));
+    assert!(!options.apply_overrides(Config::ch8().with_cdp1802(true)).cdp1802());
+}
+
+#[test]
+fn command_line_wins() {
+    let settings = Settings::parse(r#"{ "tickrate": 30, "theme": "amber" }"#).unwrap();
+    let options = options(&settings, &["rom.ch8", "--tickrate", "100"]).unwrap();
+    assert_eq!(options.tickrate, Some(100));
+    assert!(options.palette.is_some());
+}
+
+#[test]
+fn quirks_as_object() {
+    let settings = Settings::parse(r#"{ "quirks": { "shifting": true, "jumping": true } }"#).unwrap();
+    let quirks = Quirks::builder().shifting(true).jumping(true).build();
+    assert_eq!(settings.quirks, Some(QuirkSetting::Quirks(quirks.clone())));
+
+    let options = options(&settings, &["rom.ch8"]).unwrap();
+    assert_eq!(options.quirks.unwrap().parse::<Quirks>(), Ok(quirks));
+}
+
+#[test]
+fn rejects_unknown_settings() {
+    assert!(Settings::parse(r#"{ "tickrate": 30, "speed": 2 }"#).is_err());
+    assert!(Settings::parse(r#"{ "quirks": { "shifty": true } }"#).is_err());
+    // values are checked like on the command line
+    let settings = Settings::parse(r#"{ "theme": "pink" }"#).unwrap();
+    assert!(options(&settings, &["rom.ch8"]).is_err());
+}
+
+#[test]
+fn loads_the_file_given_on_the_command_line() {
+    let path = std::env::temp_dir().join(format!("yac8-config-{}.json", std::process::id()));
+    fs::write(&path, r#"{ "scale": 4 }"#).unwrap();
+    let args: Vec<String> = ["--config", path.to_str().unwrap(), "rom.ch8"].iter().map(|arg| arg.to_string()).collect();
+
+    let settings = Settings::find(&args).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(settings.scale, Some(4));
+    assert_eq!(parse_args(settings.to_args().into_iter().chain(args)).unwrap().window.scale, 4);
+    assert!(Settings::find(&["--config".to_string(), path.to_str().unwrap().to_string()]).is_err());
+}
+
+#[test]
+fn rom_sections_sit_between_the_file_and_the_command_line() {
+    let settings = Settings::parse(r#"{
+        "persistence": "deflicker",
+        "tickrate": 30,
+        "roms": { "5A8E1CADA60DDDD388AC954852AAC63F284589FF": { "persistence": "phosphor", "keymap": "arrows" } }
+    }"#).unwrap();
+    let parse = |hash: &str, cli: &[&str]| {
+        parse_args(settings.args_for(hash).into_iter().chain(cli.iter().map(|arg| arg.to_string()))).unwrap()
+    };
+
+    let options = parse("5a8e1cada60dddd388ac954852aac63f284589ff", &["rom.ch8"]);
+    assert_eq!(options.persistence, Some(Persistence::Phosphor(60)));
+    assert_eq!(options.tickrate, Some(30));
+    assert_eq!(parse("5a8e1cada60dddd388ac954852aac63f284589ff", &["rom.ch8", "--persistence", "off"]).persistence, Some(Persistence::Off));
+    assert_eq!(parse("0000", &["rom.ch8"]).persistence, Some(Persistence::Deflicker));
+
+    assert!(Settings::parse(r#"{ "roms": { "0000": { "roms": {} } } }"#).is_ok());
+    assert!(Settings::parse(r#"{ "roms": { "0000": { "roms": { "1111": {} } } } }"#).is_err());
+    assert!(Settings::parse(r#"{ "roms": { "0000": { "database": "programs.json" } } }"#).is_err());
+}
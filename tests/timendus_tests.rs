@@ -1,23 +1,25 @@
-use yac8::chip8_runner::{run_for_cycles, run_stop_on_blocked};
+use yac8::chip8_runner::{replay_movie, run_for_cycles, run_stop_on_blocked, HeadlessRunner};
+use yac8::input_script::InputScript;
+use yac8::timing::TimingMode;
 
-use crate::common::test_utils::{ch8_with_test_rom, dump_vram_when_blocked, schip_with_test_rom, xochip_with_test_rom};
-use crate::common::vram_dumps::{EX9E_NOT_PRESSED, EXA1_NOT_PRESSED, FLAGS, IBM_LOGO, OPCODES, QUIRKS_CH8, QUIRKS_SCHIP, QUIRKS_XOCHIP};
+use crate::common::snapshots::assert_snapshot;
+use crate::common::test_utils::{ch8_with_test_rom, ch8_with_test_rom_and_timing, dump_vram_when_blocked, movie_with_test_rom, schip_with_test_rom, xochip_with_test_rom};
 
 mod common;
 
 #[test]
 fn ibm_logo() {
-    assert_eq!(dump_vram_when_blocked(&[1], 0x1FF), IBM_LOGO);
+    assert_snapshot("ibm_logo", &dump_vram_when_blocked(&[1], 0x1FF));
 }
 
 #[test]
 fn opcodes() {
-    assert_eq!(dump_vram_when_blocked(&[2], 0x1FF), OPCODES);
+    assert_snapshot("opcodes", &dump_vram_when_blocked(&[2], 0x1FF));
 }
 
 #[test]
 fn flags() {
-    assert_eq!(dump_vram_when_blocked(&[3], 0x1FF), FLAGS);
+    assert_snapshot("flags", &dump_vram_when_blocked(&[3], 0x1FF));
 }
 
 //TODO macro
@@ -27,7 +29,16 @@ fn quirks_ch8() {
     chip8.load_to_memory(&[4], 0x1ff);
     chip8.load_to_memory(&[1], 0x1fe);
     run_stop_on_blocked(&mut chip8);
-    assert_eq!(chip8.vram, QUIRKS_CH8);
+    assert_snapshot("quirks_ch8", &chip8.vram);
+}
+
+#[test]
+fn quirks_ch8_vip_timing() {
+    let mut chip8 = ch8_with_test_rom_and_timing(TimingMode::CosmacVip);
+    chip8.load_to_memory(&[4], 0x1ff);
+    chip8.load_to_memory(&[1], 0x1fe);
+    run_stop_on_blocked(&mut chip8);
+    assert_snapshot("quirks_ch8", &chip8.vram);
 }
 
 #[test]
@@ -36,7 +47,7 @@ fn quirks_schip() {
     super_chip.load_to_memory(&[4], 0x1ff);
     super_chip.load_to_memory(&[2], 0x1fe);
     run_stop_on_blocked(&mut super_chip);
-    assert_eq!(super_chip.vram, QUIRKS_SCHIP);
+    assert_snapshot("quirks_schip", &super_chip.vram);
 }
 
 #[test]
@@ -45,25 +56,51 @@ fn quirks_xochip() {
     xo_chip.load_to_memory(&[4], 0x1ff);
     xo_chip.load_to_memory(&[3], 0x1fe);
     run_stop_on_blocked(&mut xo_chip);
-    assert_eq!(xo_chip.vram, QUIRKS_XOCHIP);
+    assert_snapshot("quirks_xochip", &xo_chip.vram);
 }
 
-//TODO test with pressed keys
 #[test]
 fn ex9e_not_pressed() {
     let mut chip8 = ch8_with_test_rom();
     chip8.load_to_memory(&[5], 0x1ff);
     chip8.load_to_memory(&[1], 0x1fe);
     run_for_cycles(&mut chip8, 1000);
-    assert_eq!(chip8.vram, EX9E_NOT_PRESSED);
+    assert_snapshot("ex9e_not_pressed", &chip8.vram);
 }
 
-//TODO test with pressed keys
 #[test]
 fn exa1_not_pressed() {
     let mut chip8 = ch8_with_test_rom();
     chip8.load_to_memory(&[5], 0x1ff);
     chip8.load_to_memory(&[2], 0x1fe);
     run_for_cycles(&mut chip8, 1000);
-    assert_eq!(chip8.vram, EXA1_NOT_PRESSED);
+    assert_snapshot("exa1_not_pressed", &chip8.vram);
+}
+
+#[test]
+fn ex9e_pressed() {
+    let (mut chip8, movie) = movie_with_test_rom("ex9e_pressed.movie");
+    chip8.load_to_memory(&[5], 0x1ff);
+    chip8.load_to_memory(&[1], 0x1fe);
+    replay_movie(&mut chip8, &movie).unwrap();
+    assert_snapshot("ex9e_pressed", &chip8.vram);
+}
+
+#[test]
+fn exa1_pressed() {
+    let (mut chip8, movie) = movie_with_test_rom("exa1_pressed.movie");
+    chip8.load_to_memory(&[5], 0x1ff);
+    chip8.load_to_memory(&[2], 0x1fe);
+    replay_movie(&mut chip8, &movie).unwrap();
+    assert_snapshot("exa1_pressed", &chip8.vram);
+}
+
+#[test]
+fn fx0a() {
+    let mut chip8 = ch8_with_test_rom();
+    chip8.load_to_memory(&[5], 0x1ff);
+    chip8.load_to_memory(&[3], 0x1fe);
+    let script: InputScript = "frame 60: press 5; frame 70: release 5".parse().unwrap();
+    HeadlessRunner::new().script(script).max_frames(120).run(&mut chip8);
+    assert_snapshot("fx0a", &chip8.vram);
 }
\ No newline at end of file